@@ -10,9 +10,38 @@ mp_state = {
     }
 }
 
+-- update(delta, time) is called tick_rate times per second, independent of the frame rate
+mp_config = {
+    tick_rate = 30,
+}
+
+-- sampled every tick and drawn in the plots window, `mp.plot(name, value)` adds samples too
+mp_plots = {
+    length = 300,
+    { name = "num", bind = "num" },
+    { name = "fps", value = function() return mp_state.fps end, type = "histogram", min = 0 },
+}
+
 mp_led = {
-    [0] = true,
-    [255] = true,
+    width = 16,
+    height = 8,
+    palette = {
+        { 1, 0.3, 0.3 },
+        { 0.3, 1, 0.3 },
+    },
+    -- a sequence, the cell at x, y is cells[y * width + x + 1]
+    cells = {
+        [1] = true,
+        [16] = 1,
+        [128] = 2,
+    },
+    -- left paints, right erases, drag to paint across cells
+    on_click = function(x, y, button)
+        mp_led.cells[y * mp_led.width + x + 1] = button == "left"
+    end,
+    on_hover = function(x, y)
+        mp_state.led_hover = string.format("%d, %d", x, y)
+    end,
 }
 
 mp_selection = {
@@ -25,7 +54,6 @@ mp_selection = {
             else
                 mp_selection[1].text = "reload button"
             end
-            mp_reload_selection()
         end
     },
     {
@@ -40,29 +68,80 @@ mp_selection = {
             mp_state.num = mp_state.num - 2
         end
     },
+    {
+        text = function() return "halve num ("..mp_state.num..")" end,
+        enabled = function() return mp_state.num >= 2 end,
+        tooltip = "only while num is at least 2",
+        callback = function()
+            mp_state.num = mp_state.num // 2
+        end,
+    },
     {
         text = "error",
         callback = function()
             not_exist.field = 3
         end,
     },
+    {
+        text = "log",
+        callback = function()
+            mp.log("num is", mp_state.num)
+        end,
+    },
     {
         text = "insert",
         callback = function()
             mp_state[math.random(1, 10)] = math.random(1, 10)
         end,
     },
+    {
+        type = "slider_int",
+        text = "num",
+        bind = "num",
+        min = 0,
+        max = 500,
+    },
+    {
+        type = "checkbox",
+        text = "b",
+        bind = "table.b",
+    },
+    {
+        type = "combo",
+        text = "difficulty",
+        options = { "easy", "normal", "hard" },
+        value = "normal",
+        callback = function(value)
+            print("difficulty", value)
+        end,
+    },
 }
 
 mp_show = {
-    -- {
-    --     type = mp.EShow.Rect,
-    --     pos = { x = 4, y = 4 },
-    -- }
+    {
+        type = mp.EShow.Rect,
+        pos = { x = 700, y = 450 },
+        size = { w = 120, h = 80 },
+        color = { 0.2, 0.4, 0.8 },
+    },
+    {
+        type = mp.EShow.Circle,
+        pos = { x = 760, y = 490 },
+        radius = 30,
+        color = { 1, 0.8, 0.2 },
+        mode = "stroke",
+        width = 3,
+        z = 1,
+    },
+    {
+        type = mp.EShow.Text,
+        pos = { x = 700, y = 420 },
+        text = "maple",
+    },
 }
 
 local function update_led(time_since_start)
-    mp_led[1] = time_since_start % 1 > 0.5
+    mp_led.cells[1] = time_since_start % 1 > 0.5
 end
 
 function update(delta, time_since_start)
@@ -81,4 +160,14 @@ function awake()
         text = "awake selection",
         callback = function() print("emm") end,
     })
-end
\ No newline at end of file
+end
+function on_key_down(key, mods, is_repeat)
+    mp_state.last_key = key
+    if key == "space" and mods.shift then
+        mp_state.num = 0
+    end
+end
+
+function on_mouse_down(button, x, y)
+    mp_state.last_click = string.format("%s %d, %d", button, x, y)
+end
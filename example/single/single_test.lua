@@ -0,0 +1,17 @@
+function test_add_num()
+    local num = mp_state.num
+    mp_selection[2].callback()
+    mp.assert_eq(mp_state.num, num + 1)
+end
+
+function test_awake_adds_selection()
+    mp.assert_eq(mp_selection[#mp_selection].text, "awake selection")
+end
+
+mp_tests = {
+    update_sets_fps = function()
+        update(0.5, 1)
+        mp.assert_near(mp_state.delta, 0.5)
+        mp.assert_eq(mp_state.fps, 2)
+    end,
+}
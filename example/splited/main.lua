@@ -1,4 +1,4 @@
-require "./state"
+require "state"
 
 mp_state = {
     a = state.a,
@@ -0,0 +1,98 @@
+mp_state = {
+    gold = 100,
+    inventory = {},
+    log = "",
+}
+
+mp_selection = {}
+
+local function buy(name, cost)
+    return function()
+        if mp_state.gold < cost then
+            mp_state.log = "not enough gold for "..name
+            return
+        end
+        mp_state.gold = mp_state.gold - cost
+        table.insert(mp_state.inventory, name)
+        mp_state.log = "bought "..name
+    end
+end
+
+mp_windows = {
+    {
+        title = "shop",
+        size = { 460, 220 },
+        position = { 360, 50 },
+        flags = { "no_resize" },
+        content = {
+            { type = "text", text = "gold", bind = "gold" },
+            { type = "separator" },
+            {
+                type = "tab_bar",
+                content = {
+                    {
+                        type = "tab_item",
+                        text = "gear",
+                        content = {
+                            { text = "sword (60)", callback = buy("sword", 60) },
+                            { type = "same_line" },
+                            { text = "shield (40)", callback = buy("shield", 40) },
+                        },
+                    },
+                    {
+                        type = "tab_item",
+                        text = "potions",
+                        content = {
+                            {
+                                type = "columns",
+                                count = 2,
+                                content = {
+                                    { text = "potion (10)", callback = buy("potion", 10) },
+                                    { text = "elixir (30)", callback = buy("elixir", 30) },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        },
+    },
+    {
+        title = "inventory",
+        position = { 840, 50 },
+        flags = { "auto_resize" },
+        content = {
+            { type = "text", text = "items are in mp_state.inventory" },
+            {
+                type = "slider_int",
+                text = "gold",
+                bind = "gold",
+                min = 0,
+                max = 1000,
+            },
+            {
+                type = "canvas",
+                size = { 200, 60 },
+                shapes = {
+                    { type = mp.EShow.Rect, pos = { 0, 0 }, size = { 200, 60 }, color = { 0.2, 0.2, 0.2 } },
+                    { type = mp.EShow.Circle, pos = { 30, 30 }, radius = 20, color = { 1, 0.8, 0.2 } },
+                    { type = mp.EShow.Text, pos = { 60, 20 }, text = "treasure" },
+                },
+            },
+        },
+    },
+    {
+        title = "log",
+        position = { 360, 290 },
+        size = { 400, 100 },
+        content = {
+            { type = "text", text = "last", bind = "log" },
+        },
+    },
+}
+
+function update(delta, time_since_start)
+end
+
+function awake()
+end
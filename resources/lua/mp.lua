@@ -0,0 +1,79 @@
+-- `mp` itself is created on the rust side, see `host.rs`
+
+-- kept for scripts written before `mp.reload_selection`
+mp_reload_selection = mp.reload_selection
+mp_select = mp.select
+
+mp.EShow = {
+    Rect = "rect",
+    Circle = "circle",
+    Line = "line",
+    Polygon = "polygon",
+    Text = "text",
+}
+
+local function deep_equal(a, b)
+    if a == b then
+        return true
+    end
+    if type(a) ~= "table" or type(b) ~= "table" then
+        return false
+    end
+    for k, v in pairs(a) do
+        if not deep_equal(v, b[k]) then
+            return false
+        end
+    end
+    for k, _ in pairs(b) do
+        if a[k] == nil then
+            return false
+        end
+    end
+    return true
+end
+
+mp.assert_eq = function(actual, expected, message)
+    if not deep_equal(actual, expected) then
+        error(string.format("%sexpected %s, got %s",
+            message and (message .. ": ") or "", tostring(expected), tostring(actual)), 2)
+    end
+end
+
+mp.assert_near = function(actual, expected, epsilon, message)
+    epsilon = epsilon or 1e-6
+    if type(actual) ~= "number" or math.abs(actual - expected) > epsilon then
+        error(string.format("%sexpected %s (+-%s), got %s",
+            message and (message .. ": ") or "", tostring(expected), tostring(epsilon), tostring(actual)), 2)
+    end
+end
+
+__mp_input = {
+    keys = {},
+    buttons = {},
+    mouse = { x = 0, y = 0 },
+}
+
+mp.is_key_down = function(key)
+    return __mp_input.keys[key] == true
+end
+
+mp.is_mouse_down = function(button)
+    return __mp_input.buttons[button] == true
+end
+
+mp.mouse_pos = function()
+    return __mp_input.mouse.x, __mp_input.mouse.y
+end
+
+-- math.random goes through the seeded rng of `mp`, so a run can be replayed
+function math.random(m, n)
+    if m == nil then
+        return mp.random_float()
+    end
+    if n == nil then
+        return mp.random_int(1, m)
+    end
+    return mp.random_int(m, n)
+end
+
+math.randomseed = mp.seed
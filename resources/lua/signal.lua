@@ -1,5 +0,0 @@
-__mp_signals = {}
-
-mp_reload_selection = function()
-    table.insert(__mp_signals, 1)
-end
\ No newline at end of file
@@ -0,0 +1,99 @@
+use imgui::{im_str, Slider};
+
+use ggez::event::KeyCode;
+
+const MIN_TIME_SCALE: f32 = 0.1;
+const MAX_TIME_SCALE: f32 = 10.0;
+
+/// the virtual time lua sees through `update(delta, time)`.
+/// every tick is `1 / tick_rate` long before scaling, this replaced the optional
+/// fixed step of the control window, which is always on now
+pub struct Clock {
+    pub paused: bool,
+    pub time_scale: f32,
+    step_requested: bool,
+    time: f64,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Clock {
+            paused: false,
+            time_scale: 1.0,
+            step_requested: false,
+            time: 0.0,
+        }
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// advance one tick while paused
+    pub fn step(&mut self) {
+        self.paused = true;
+        self.step_requested = true;
+    }
+
+    /// false while paused, unless a step was requested
+    pub fn take_tick(&mut self) -> bool {
+        if self.paused {
+            if !self.step_requested {
+                return false;
+            }
+            self.step_requested = false;
+        }
+        true
+    }
+
+    /// the scaled delta of a tick, `None` while paused
+    pub fn advance(&mut self, tick_delta: f64) -> Option<f64> {
+        if !self.take_tick() {
+            return None;
+        }
+        let delta = tick_delta * f64::from(self.time_scale);
+        self.time += delta;
+        Some(delta)
+    }
+
+    /// a recorded delta already has the time scale of the recording applied
+    pub fn advance_recorded(&mut self, delta: f64) {
+        self.time += delta;
+    }
+
+    /// F5 pause/resume, F6 step, F7 slower, F8 faster, F9 normal speed.
+    /// returns false for keys that aren't clock hotkeys
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::F5 => self.paused = !self.paused,
+            KeyCode::F6 => self.step(),
+            KeyCode::F7 => self.time_scale = (self.time_scale / 2.0).max(MIN_TIME_SCALE),
+            KeyCode::F8 => self.time_scale = (self.time_scale * 2.0).min(MAX_TIME_SCALE),
+            KeyCode::F9 => self.time_scale = 1.0,
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn render(&mut self, ui: &imgui::Ui, tick_rate: u32) {
+        let label = if self.paused {
+            im_str!("resume")
+        } else {
+            im_str!("pause")
+        };
+        if ui.button(label, [0.0, 0.0]) {
+            self.paused = !self.paused;
+        }
+        ui.same_line(0.0);
+        if ui.button(im_str!("step"), [0.0, 0.0]) {
+            self.step();
+        }
+        ui.same_line(0.0);
+        ui.text(&im_str!("time {:.2}s", self.time));
+
+        Slider::new(im_str!("speed"), MIN_TIME_SCALE..=MAX_TIME_SCALE)
+            .display_format(im_str!("%.1fx"))
+            .build(ui, &mut self.time_scale);
+        ui.text(&im_str!("{} ticks per second", tick_rate));
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::Instant;
+
+use imgui::{im_str, ChildWindow, TreeNode};
+
+const MAX_ENTRIES: usize = 500;
+const ERROR_COLOR: [f32; 4] = [1.0, 0.4, 0.4, 1.0];
+const LOG_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+const DIM_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConsoleLevel {
+    Log,
+    Error,
+    /// a line typed in the repl followed by its results, never collapsed
+    Eval,
+}
+
+struct ConsoleEntry {
+    level: ConsoleLevel,
+    message: String,
+    /// `file.lua:12` of the first frame mentioned in the message
+    location: Option<String>,
+    /// seconds since the console was created, of the last occurrence
+    time: f32,
+    count: u32,
+}
+
+/// the full text of an error, rlua keeps the traceback of callback errors apart
+pub fn error_text(error: &rlua::Error) -> String {
+    match error {
+        rlua::Error::CallbackError { traceback, cause } => {
+            format!("{}\n{}", error_text(cause), traceback)
+        }
+        _ => format!("{}", error),
+    }
+}
+
+/// finds the first `<file>.lua:<line>:` in a lua error message
+fn error_location(message: &str) -> Option<String> {
+    for (pos, _) in message.match_indices(".lua:") {
+        let line_start = pos + ".lua:".len();
+        let digits = message[line_start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits == 0 {
+            continue;
+        }
+        let file_start = message[..pos]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace() || *c == '"' || *c == '[')
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        return Some(String::from(&message[file_start..line_start + digits]));
+    }
+    None
+}
+
+pub struct Console {
+    entries: Vec<ConsoleEntry>,
+    start: Instant,
+    scroll_to_bottom: bool,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Console {
+            entries: vec![],
+            start: Instant::now(),
+            scroll_to_bottom: false,
+        }
+    }
+
+    /// identical messages are collapsed into one entry with a count
+    pub fn push(&mut self, level: ConsoleLevel, message: String) {
+        let time = self.start.elapsed().as_secs_f32();
+        let existing = match level {
+            ConsoleLevel::Eval => None,
+            _ => self
+                .entries
+                .iter_mut()
+                .find(|entry| entry.level == level && entry.message == message),
+        };
+        if let Some(entry) = existing {
+            entry.count += 1;
+            entry.time = time;
+            return;
+        }
+        self.scroll_to_bottom = true;
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(ConsoleEntry {
+            level,
+            location: error_location(&message),
+            message,
+            time,
+            count: 1,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// returns true when the user asked to bring the status window back.
+    /// `footer_height` is kept free below the entries for the repl
+    pub fn render(&mut self, ui: &imgui::Ui, footer_height: f32) -> bool {
+        if ui.small_button(im_str!("clear")) {
+            self.clear();
+        }
+        ui.same_line(0.0);
+        let show_status = ui.small_button(im_str!("show status"));
+        ui.separator();
+
+        let entries = &self.entries;
+        let scroll_to_bottom = self.scroll_to_bottom;
+        ChildWindow::new(im_str!("entries"))
+            .size([0.0, -footer_height])
+            .build(ui, || {
+                for (i, entry) in entries.iter().enumerate() {
+                    render_entry(ui, i, entry);
+                }
+                if scroll_to_bottom {
+                    ui.set_scroll_here_y();
+                }
+            });
+        self.scroll_to_bottom = false;
+        show_status
+    }
+}
+
+fn render_entry(ui: &imgui::Ui, i: usize, entry: &ConsoleEntry) {
+    let mut lines = entry.message.lines();
+    let first_line = lines.next().unwrap_or("");
+    if entry.level == ConsoleLevel::Eval {
+        ui.text_colored(DIM_COLOR, &im_str!("{}", first_line));
+        for line in lines {
+            ui.text(&im_str!("{}", line));
+        }
+        return;
+    }
+
+    let color = match entry.level {
+        ConsoleLevel::Error => ERROR_COLOR,
+        _ => LOG_COLOR,
+    };
+    let count = if entry.count > 1 {
+        format!(" x{}", entry.count)
+    } else {
+        String::new()
+    };
+    ui.text_colored(DIM_COLOR, &im_str!("[{:8.2}s]{}", entry.time, count));
+    if let Some(location) = &entry.location {
+        ui.same_line(0.0);
+        ui.text_colored(DIM_COLOR, &im_str!("{}", location));
+    }
+    ui.text_colored(color, &im_str!("{}", first_line));
+    let rest = lines.collect::<Vec<_>>();
+    if !rest.is_empty() {
+        TreeNode::new(&im_str!("traceback##{}", i)).build(ui, || {
+            ui.text_wrapped(&im_str!("{}", rest.join("\n")));
+        });
+    }
+    ui.separator();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_location_finds_file_and_line() {
+        let message = "runtime error: [string \"main.lua\"]:3: oops";
+        assert_eq!(error_location(message), None);
+        let message = "runtime error: scripts/main.lua:12: attempt to call a nil value";
+        assert_eq!(
+            error_location(message),
+            Some(String::from("scripts/main.lua:12"))
+        );
+    }
+
+    #[test]
+    fn error_location_after_multibyte_separator() {
+        let message = "错误　main.lua:7: oops";
+        assert_eq!(error_location(message), Some(String::from("main.lua:7")));
+    }
+}
@@ -0,0 +1,206 @@
+use rlua::{Table, Value};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DumpFormat {
+    Json,
+    Lua,
+}
+
+impl DumpFormat {
+    pub fn from_name(name: &str) -> Option<DumpFormat> {
+        match name {
+            "json" => Some(DumpFormat::Json),
+            "lua" => Some(DumpFormat::Lua),
+            _ => None,
+        }
+    }
+}
+
+fn escape_string(s: &str, format: DumpFormat) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => match format {
+                DumpFormat::Json => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                DumpFormat::Lua => escaped.push_str(&format!("\\{}", c as u32)),
+            },
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// lua output keeps the fraction so `1.0` loads back as a float, not the integer `1`
+fn dump_number(n: f64, format: DumpFormat) -> String {
+    if n.is_finite() && format == DumpFormat::Lua {
+        format!("{:?}", n)
+    } else if n.is_finite() {
+        format!("{}", n)
+    } else if format == DumpFormat::Json {
+        String::from("null")
+    } else if n.is_nan() {
+        String::from("0/0")
+    } else if n > 0.0 {
+        String::from("math.huge")
+    } else {
+        String::from("-math.huge")
+    }
+}
+
+/// keys sorted so the output is stable between runs
+fn sorted_pairs<'lua>(table: Table<'lua>) -> rlua::Result<Vec<(Value<'lua>, Value<'lua>)>> {
+    let mut pairs = table
+        .pairs::<Value, Value>()
+        .collect::<rlua::Result<Vec<_>>>()?;
+    pairs.sort_by(|(a, _), (b, _)| dump_key_order(a).cmp(&dump_key_order(b)));
+    Ok(pairs)
+}
+
+fn dump_key_order(key: &Value) -> (u8, i64, String) {
+    match key {
+        Value::Integer(i) => (0, *i, String::new()),
+        Value::Number(n) => (1, 0, format!("{}", n)),
+        Value::String(s) => (2, 0, String::from(s.to_str().unwrap_or(""))),
+        _ => (3, 0, String::new()),
+    }
+}
+
+/// a table whose sorted keys are exactly 1..n
+fn is_sequence(pairs: &[(Value, Value)]) -> bool {
+    pairs.iter().enumerate().all(|(i, (key, _))| match key {
+        Value::Integer(k) => *k == i as i64 + 1,
+        _ => false,
+    })
+}
+
+fn dump_key(key: &Value, format: DumpFormat) -> String {
+    match (key, format) {
+        (Value::String(s), DumpFormat::Lua) => {
+            let s = s.to_str().unwrap_or("");
+            if is_identifier(s) {
+                String::from(s)
+            } else {
+                format!("[{}]", escape_string(s, format))
+            }
+        }
+        (Value::String(s), DumpFormat::Json) => escape_string(s.to_str().unwrap_or(""), format),
+        (Value::Integer(i), DumpFormat::Lua) => format!("[{}]", i),
+        (Value::Number(n), DumpFormat::Lua) => format!("[{}]", dump_number(*n, format)),
+        (Value::Integer(i), DumpFormat::Json) => format!("\"{}\"", i),
+        (Value::Number(n), DumpFormat::Json) => format!("\"{}\"", n),
+        (other, DumpFormat::Lua) => format!("[{}]", escape_string(&placeholder(other), format)),
+        (other, DumpFormat::Json) => escape_string(&placeholder(other), format),
+    }
+}
+
+fn placeholder(value: &Value) -> String {
+    match value {
+        Value::Function(_) => String::from("<function>"),
+        Value::Thread(_) => String::from("<thread>"),
+        Value::UserData(_) | Value::LightUserData(_) => String::from("<userdata>"),
+        Value::Error(e) => format!("<error: {}>", e),
+        _ => String::from("<unknown>"),
+    }
+}
+
+/// pretty print a lua value as json or a lua literal.
+/// functions and userdata are written as placeholder strings
+pub fn dump_value(value: Value, format: DumpFormat, depth: usize) -> rlua::Result<String> {
+    let dumped = match value {
+        Value::Nil => String::from(match format {
+            DumpFormat::Json => "null",
+            DumpFormat::Lua => "nil",
+        }),
+        Value::Boolean(b) => format!("{}", b),
+        Value::Integer(i) => format!("{}", i),
+        Value::Number(n) => dump_number(n, format),
+        Value::String(s) => escape_string(s.to_str().unwrap_or(""), format),
+        Value::Table(table) => {
+            let pairs = sorted_pairs(table)?;
+            if pairs.is_empty() {
+                return Ok(String::from("{}"));
+            }
+            let is_sequence = is_sequence(&pairs);
+            let indent = "  ".repeat(depth + 1);
+            let mut lines = vec![];
+            for (key, value) in pairs {
+                let value = dump_value(value, format, depth + 1)?;
+                if is_sequence {
+                    lines.push(format!("{}{}", indent, value));
+                } else {
+                    let separator = match format {
+                        DumpFormat::Json => ": ",
+                        DumpFormat::Lua => " = ",
+                    };
+                    lines.push(format!(
+                        "{}{}{}{}",
+                        indent,
+                        dump_key(&key, format),
+                        separator,
+                        value
+                    ));
+                }
+            }
+            let (open, close) = match (format, is_sequence) {
+                (DumpFormat::Json, true) => ("[", "]"),
+                _ => ("{", "}"),
+            };
+            format!(
+                "{}\n{}\n{}{}",
+                open,
+                lines.join(",\n"),
+                "  ".repeat(depth),
+                close
+            )
+        }
+        other => escape_string(&placeholder(&other), format),
+    };
+    Ok(dumped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lua_numbers_stay_floats() {
+        assert_eq!(dump_number(1.0, DumpFormat::Lua), "1.0");
+        assert_eq!(dump_number(-0.5, DumpFormat::Lua), "-0.5");
+        assert_eq!(dump_number(1.0, DumpFormat::Json), "1");
+    }
+
+    #[test]
+    fn dumped_floats_load_back_as_floats() {
+        rlua::Lua::new().context(|lua_ctx| {
+            let table = lua_ctx.create_table().unwrap();
+            table.set("x", 2.0).unwrap();
+            table.set("n", 2).unwrap();
+            let source = dump_value(Value::Table(table), DumpFormat::Lua, 0).unwrap();
+            let loaded: Table = lua_ctx.load(&source).eval().unwrap();
+            assert!(matches!(
+                loaded.get::<_, Value>("x").unwrap(),
+                Value::Number(_)
+            ));
+            assert!(matches!(
+                loaded.get::<_, Value>("n").unwrap(),
+                Value::Integer(2)
+            ));
+        });
+    }
+}
@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::path::Path;
+
+use crate::dump::DumpFormat;
+use crate::host::HostCommand;
+use crate::lua::MpLua;
+use crate::project::Project;
+use crate::record::read_recording;
+
+pub struct HeadlessOptions {
+    pub frames: u32,
+    pub delta: f64,
+    pub format: DumpFormat,
+    pub seed: Option<u64>,
+}
+
+/// returns false once the script asked to quit. a failing command is only
+/// reported by `tick_commands`, so it is turned into an error here
+fn tick_commands(mp_lua: &mut MpLua) -> Result<bool, Box<dyn Error>> {
+    let commands = mp_lua.tick_commands()?;
+    mp_lua.check_errors()?;
+    Ok(handle_host_commands(commands))
+}
+
+fn handle_host_commands(commands: Vec<HostCommand>) -> bool {
+    let mut is_running = true;
+    for command in commands {
+        match command {
+            HostCommand::Quit => is_running = false,
+            // there is no window to set the title of
+            HostCommand::SetTitle(_) => {}
+        }
+    }
+    is_running
+}
+
+/// run the prototype without a window: `awake`, then `frames` ticks of `update`
+/// with a fixed delta, then print the final `mp_state`.
+/// any lua error is returned, so the process exits with a non-zero status
+pub fn run_headless(input_path: &str, options: &HeadlessOptions) -> Result<(), Box<dyn Error>> {
+    let project = Project::open(input_path)?;
+    let mut mp_lua = match options.seed {
+        Some(seed) => MpLua::with_seed(project, seed)?,
+        None => MpLua::new(project)?,
+    };
+    mp_lua.awake()?;
+    // a broken `mp_selection` or window is only reported by `awake`
+    mp_lua.check_errors()?;
+    let mut is_running = tick_commands(&mut mp_lua)?;
+
+    // through the clock like a windowed run, so `time` and the history match
+    for _ in 0..options.frames {
+        if !is_running {
+            break;
+        }
+        mp_lua.tick(options.delta)?;
+        // plot sampling errors are reported without failing the tick
+        mp_lua.check_errors()?;
+        is_running = tick_commands(&mut mp_lua)?;
+    }
+
+    println!("{}", mp_lua.dump_state(options.format)?);
+    Ok(())
+}
+
+/// feed a `.mpr` recording through the script without a window,
+/// then print the final `mp_state`
+pub fn replay_headless(record_path: &str, format: DumpFormat) -> Result<(), Box<dyn Error>> {
+    let events = read_recording(Path::new(record_path))?;
+    let mut mp_lua = MpLua::from_recording(&events[0])?;
+    mp_lua.awake()?;
+    mp_lua.check_errors()?;
+    for event in &events {
+        let commands = mp_lua.replay_event(event)?;
+        mp_lua.check_errors()?;
+        if !handle_host_commands(commands) {
+            break;
+        }
+    }
+
+    println!("{}", mp_lua.dump_state(format)?);
+    Ok(())
+}
@@ -0,0 +1,309 @@
+use std::collections::VecDeque;
+
+use imgui::{im_str, Slider};
+
+use crate::snapshot::StateValue;
+use crate::state::{format_state_path, StateKey};
+
+/// about 16 seconds at the default 60 ticks per second
+pub const DEFAULT_HISTORY_LENGTH: usize = 1000;
+const ADDED_COLOR: [f32; 4] = [0.4, 1.0, 0.4, 1.0];
+const REMOVED_COLOR: [f32; 4] = [1.0, 0.4, 0.4, 1.0];
+const CHANGED_COLOR: [f32; 4] = [1.0, 0.9, 0.4, 1.0];
+
+pub struct HistoryFrame {
+    /// counts every recorded frame, so it stays put when old frames are dropped
+    pub id: u64,
+    /// what produced the frame, `tick` or the selection that was used
+    pub label: String,
+    pub time: f64,
+    pub state: StateValue,
+}
+
+/// `mp_state` after every tick and selection callback, the oldest frames are dropped
+pub struct History {
+    frames: VecDeque<HistoryFrame>,
+    next_id: u64,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            frames: VecDeque::with_capacity(DEFAULT_HISTORY_LENGTH),
+            next_id: 0,
+            capacity: DEFAULT_HISTORY_LENGTH,
+        }
+    }
+
+    /// a smaller capacity drops the oldest frames right away
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.frames.len() > self.capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn record(&mut self, label: String, time: f64, state: StateValue) {
+        if self.frames.len() >= self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(HistoryFrame {
+            id: self.next_id,
+            label,
+            time,
+            state,
+        });
+        self.next_id += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&HistoryFrame> {
+        self.frames.get(index)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StateChange {
+    Added(String),
+    Removed(String),
+    Changed(String, String),
+}
+
+fn short_value(value: &StateValue) -> String {
+    match value {
+        StateValue::Boolean(b) => format!("{}", b),
+        StateValue::Integer(i) => format!("{}", i),
+        StateValue::Number(n) => format!("{}", n),
+        StateValue::Str(s) => format!("{:?}", s),
+        StateValue::Table(_) => String::from("table"),
+    }
+}
+
+/// the changed leaves from `old` to `new`, sorted by path
+pub fn diff_states(old: &StateValue, new: &StateValue) -> Vec<(String, StateChange)> {
+    let mut changes = vec![];
+    diff_values(old, new, &mut vec![], &mut changes);
+    changes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    changes
+}
+
+fn diff_values(
+    old: &StateValue,
+    new: &StateValue,
+    path: &mut Vec<StateKey>,
+    changes: &mut Vec<(String, StateChange)>,
+) {
+    match (old, new) {
+        (StateValue::Table(old_fields), StateValue::Table(new_fields)) => {
+            for (key, old_value) in old_fields {
+                path.push(key.clone());
+                match new_fields.iter().find(|(new_key, _)| new_key == key) {
+                    Some((_, new_value)) => diff_values(old_value, new_value, path, changes),
+                    None => changes.push((
+                        format_state_path(path),
+                        StateChange::Removed(short_value(old_value)),
+                    )),
+                }
+                path.pop();
+            }
+            for (key, new_value) in new_fields {
+                if !old_fields.iter().any(|(old_key, _)| old_key == key) {
+                    path.push(key.clone());
+                    changes.push((
+                        format_state_path(path),
+                        StateChange::Added(short_value(new_value)),
+                    ));
+                    path.pop();
+                }
+            }
+        }
+        _ if old != new => changes.push((
+            format_state_path(path),
+            StateChange::Changed(short_value(old), short_value(new)),
+        )),
+        _ => {}
+    }
+}
+
+/// the scrubber state of the timeline window
+pub struct Timeline {
+    frame: i32,
+    compare: i32,
+    /// keep the scrubber on the newest frame
+    follow: bool,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Timeline {
+            frame: 0,
+            compare: 0,
+            follow: true,
+        }
+    }
+
+    /// returns the index of the frame to restore, if the user picked one
+    pub fn render(&mut self, ui: &imgui::Ui, history: &History) -> Option<usize> {
+        if history.is_empty() {
+            ui.text(im_str!("no frames recorded yet"));
+            return None;
+        }
+        let last = history.len() as i32 - 1;
+        if self.follow {
+            self.frame = last;
+            self.compare = (last - 1).max(0);
+        }
+        self.frame = self.frame.min(last).max(0);
+        self.compare = self.compare.min(last).max(0);
+
+        let mut restore = None;
+        ui.checkbox(im_str!("follow"), &mut self.follow);
+        if Slider::new(im_str!("frame"), 0..=last).build(ui, &mut self.frame) {
+            self.follow = false;
+            restore = Some(self.frame as usize);
+        }
+        if let Some(frame) = history.get(self.frame as usize) {
+            ui.text(&im_str!(
+                "#{} {} at {:.2}s",
+                frame.id,
+                frame.label,
+                frame.time
+            ));
+        }
+        if ui.button(im_str!("restore"), [0.0, 0.0]) {
+            self.follow = false;
+            restore = Some(self.frame as usize);
+        }
+        ui.separator();
+
+        if Slider::new(im_str!("compare with"), 0..=last).build(ui, &mut self.compare) {
+            self.follow = false;
+        }
+        let (old, new) = match (
+            history.get(self.compare as usize),
+            history.get(self.frame as usize),
+        ) {
+            (Some(old), Some(new)) => (old, new),
+            _ => return restore,
+        };
+        let changes = diff_states(&old.state, &new.state);
+        if changes.is_empty() {
+            ui.text(im_str!("no changes"));
+        }
+        for (path, change) in changes {
+            match change {
+                StateChange::Added(value) => {
+                    ui.text_colored(ADDED_COLOR, &im_str!("+ {} = {}", path, value))
+                }
+                StateChange::Removed(value) => {
+                    ui.text_colored(REMOVED_COLOR, &im_str!("- {} = {}", path, value))
+                }
+                StateChange::Changed(old_value, new_value) => ui.text_colored(
+                    CHANGED_COLOR,
+                    &im_str!("~ {}: {} -> {}", path, old_value, new_value),
+                ),
+            }
+        }
+        restore
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> StateKey {
+        StateKey::String(String::from(name))
+    }
+
+    #[test]
+    fn diff_lists_changed_leaves_by_path() {
+        let old = StateValue::Table(vec![
+            (key("hp"), StateValue::Integer(3)),
+            (
+                key("pos"),
+                StateValue::Table(vec![(key("x"), StateValue::Number(1.5))]),
+            ),
+            (key("gone"), StateValue::Boolean(true)),
+        ]);
+        let new = StateValue::Table(vec![
+            (key("name"), StateValue::Str(String::from("a"))),
+            (
+                key("pos"),
+                StateValue::Table(vec![
+                    (key("x"), StateValue::Number(1.5)),
+                    (StateKey::Integer(1), StateValue::Integer(2)),
+                ]),
+            ),
+            (key("hp"), StateValue::Integer(4)),
+        ]);
+        assert_eq!(
+            diff_states(&old, &new),
+            vec![
+                (
+                    String::from("gone"),
+                    StateChange::Removed(String::from("true"))
+                ),
+                (
+                    String::from("hp"),
+                    StateChange::Changed(String::from("3"), String::from("4"))
+                ),
+                (
+                    String::from("name"),
+                    StateChange::Added(String::from("\"a\""))
+                ),
+                (String::from("pos.1"), StateChange::Added(String::from("2"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_of_equal_states_is_empty() {
+        let state = StateValue::Table(vec![(key("hp"), StateValue::Integer(3))]);
+        assert!(diff_states(&state, &state.clone()).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_a_table_replaced_by_a_value() {
+        let old = StateValue::Table(vec![(key("pos"), StateValue::Table(vec![]))]);
+        let new = StateValue::Table(vec![(key("pos"), StateValue::Integer(0))]);
+        assert_eq!(
+            diff_states(&old, &new),
+            vec![(
+                String::from("pos"),
+                StateChange::Changed(String::from("table"), String::from("0"))
+            )]
+        );
+    }
+
+    #[test]
+    fn history_drops_the_oldest_frames() {
+        let mut history = History::new();
+        for i in 0..=DEFAULT_HISTORY_LENGTH {
+            history.record(String::from("tick"), i as f64, StateValue::Table(vec![]));
+        }
+        assert_eq!(history.len(), DEFAULT_HISTORY_LENGTH);
+        assert_eq!(history.get(0).map(|frame| frame.id), Some(1));
+    }
+
+    #[test]
+    fn shrinking_the_history_keeps_the_newest_frames() {
+        let mut history = History::new();
+        for i in 0..10 {
+            history.record(String::from("tick"), i as f64, StateValue::Table(vec![]));
+        }
+        history.set_capacity(3);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.get(0).map(|frame| frame.id), Some(7));
+        history.record(String::from("tick"), 10.0, StateValue::Table(vec![]));
+        assert_eq!(history.len(), 3);
+    }
+}
@@ -0,0 +1,153 @@
+use rlua::{Integer, Table, Value, Variadic};
+
+use crate::plot::Plots;
+use crate::rng::{choice, shuffle, weighted_choice, Rng};
+use crate::shared::Shared;
+use crate::snapshot::{skipped_message, Snapshots};
+use crate::status::display_value;
+
+/// commands that need the window, handled by whoever runs the `MpLua`
+#[derive(Debug)]
+pub enum HostCommand {
+    Quit,
+    SetTitle(String),
+}
+
+#[derive(Debug)]
+pub enum Command {
+    ReloadSelection,
+    Select(usize),
+    Log(String),
+    /// a problem worth showing in the console that isn't a lua error
+    Error(String),
+    Host(HostCommand),
+}
+
+/// the rust side of the `mp` library, `resources/lua/mp.lua` adds the lua helpers.
+/// reading a missing field of `mp` is an error, so typos fail loudly
+pub fn create_mp_table<'lua>(
+    lua_ctx: rlua::Context<'lua>,
+    queue: &Shared<Vec<Command>>,
+    snapshots: &Shared<Snapshots>,
+    rng: &Shared<Rng>,
+    plots: &Shared<Plots>,
+) -> rlua::Result<Table<'lua>> {
+    let mp = lua_ctx.create_table()?;
+
+    // added or replaced items are picked up every frame, this is for edits
+    // inside an item that keeps its table, e.g. a new `max`
+    let q = queue.clone();
+    let reload_selection = lua_ctx.create_function(move |_, ()| {
+        q.lock().push(Command::ReloadSelection);
+        Ok(())
+    })?;
+    mp.set("reload_selection", reload_selection)?;
+
+    let q = queue.clone();
+    let select = lua_ctx.create_function(move |_, index: usize| {
+        q.lock().push(Command::Select(index));
+        Ok(())
+    })?;
+    mp.set("select", select)?;
+
+    let q = queue.clone();
+    let quit = lua_ctx.create_function(move |_, ()| {
+        q.lock().push(Command::Host(HostCommand::Quit));
+        Ok(())
+    })?;
+    mp.set("quit", quit)?;
+
+    let q = queue.clone();
+    let set_title = lua_ctx.create_function(move |_, title: String| {
+        q.lock().push(Command::Host(HostCommand::SetTitle(title)));
+        Ok(())
+    })?;
+    mp.set("set_title", set_title)?;
+
+    let q = queue.clone();
+    let log = lua_ctx.create_function(move |_, values: Variadic<Value>| {
+        let texts = values.iter().map(display_value).collect::<Vec<_>>();
+        q.lock().push(Command::Log(texts.join("\t")));
+        Ok(())
+    })?;
+    mp.set("log", log)?;
+
+    let q = queue.clone();
+    let store = snapshots.clone();
+    let save_state = lua_ctx.create_function(move |lua_ctx, name: String| {
+        let skipped = store.lock().save(lua_ctx, &name)?;
+        if let Some(message) = skipped_message(&name, &skipped) {
+            q.lock().push(Command::Error(message));
+        }
+        Ok(())
+    })?;
+    mp.set("save_state", save_state)?;
+
+    let store = snapshots.clone();
+    let load_state =
+        lua_ctx.create_function(move |lua_ctx, name: String| store.lock().load(lua_ctx, &name))?;
+    mp.set("load_state", load_state)?;
+
+    let r = rng.clone();
+    let seed = lua_ctx.create_function(move |_, seed: Integer| {
+        r.lock().reseed(seed as u64);
+        Ok(())
+    })?;
+    mp.set("seed", seed)?;
+
+    let r = rng.clone();
+    let get_seed = lua_ctx.create_function(move |_, ()| Ok(r.lock().seed() as Integer))?;
+    mp.set("get_seed", get_seed)?;
+
+    let r = rng.clone();
+    let random_int = lua_ctx.create_function(move |_, (a, b): (Integer, Integer)| {
+        if a > b {
+            return Err(rlua::Error::RuntimeError(format!(
+                "mp.random_int: empty range [{}, {}]",
+                a, b
+            )));
+        }
+        Ok(r.lock().range(a, b))
+    })?;
+    mp.set("random_int", random_int)?;
+
+    let r = rng.clone();
+    let random_float = lua_ctx.create_function(move |_, ()| Ok(r.lock().next_f64()))?;
+    mp.set("random_float", random_float)?;
+
+    let r = rng.clone();
+    let choice = lua_ctx.create_function(move |_, table: Table| choice(&mut r.lock(), table))?;
+    mp.set("choice", choice)?;
+
+    let r = rng.clone();
+    let weighted_choice =
+        lua_ctx.create_function(move |_, table: Table| weighted_choice(&mut r.lock(), table))?;
+    mp.set("weighted_choice", weighted_choice)?;
+
+    let r = rng.clone();
+    let shuffle = lua_ctx.create_function(move |_, table: Table| {
+        shuffle(&mut r.lock(), &table)?;
+        Ok(table)
+    })?;
+    mp.set("shuffle", shuffle)?;
+
+    let p = plots.clone();
+    let plot = lua_ctx.create_function(move |_, (name, value): (String, f32)| {
+        p.lock().push(&name, value);
+        Ok(())
+    })?;
+    mp.set("plot", plot)?;
+
+    let meta = lua_ctx.create_table()?;
+    meta.set(
+        "__index",
+        lua_ctx.create_function(|_, (_, key): (Table, Value)| -> rlua::Result<()> {
+            Err(rlua::Error::RuntimeError(format!(
+                "mp.{} does not exist",
+                display_value(&key)
+            )))
+        })?,
+    )?;
+    mp.set_metatable(Some(meta));
+    Ok(mp)
+}
@@ -7,10 +7,14 @@ use gfx_core::{handle::RenderTargetView, memory::Typed};
 use imgui::*;
 use imgui_gfx_renderer::*;
 
+use crate::history::Timeline;
+use crate::led::LedInput;
 use crate::lua::*;
+use crate::project::{Panel, UiConfig};
+use crate::repl::Repl;
 use std::time::Instant;
 
-const LED_CELL_SIZE: f32 = 16.0;
+const FILTER_CAPACITY: usize = 64;
 
 #[derive(Copy, Clone, PartialEq, Debug, Default)]
 struct MouseState {
@@ -25,10 +29,48 @@ pub struct ImGuiWrapper {
     pub renderer: Renderer<gfx_core::format::Rgba8, gfx_device_gl::Resources>,
     last_frame: Instant,
     mouse_state: MouseState,
+    status_filter: ImString,
+    led_input: LedInput,
+    /// set by the console, moves the status window back into view for a frame
+    focus_status: bool,
+    repl: Repl,
+    snapshot_name: ImString,
+    timeline: Timeline,
+    config: UiConfig,
+}
+
+fn panel_title(panel: Panel) -> &'static ImStr {
+    match panel {
+        Panel::Status => im_str!("status"),
+        Panel::Selection => im_str!("selection"),
+        Panel::Led => im_str!("led"),
+        Panel::Control => im_str!("control"),
+        Panel::Snapshot => im_str!("snapshot"),
+        Panel::Timeline => im_str!("timeline"),
+        Panel::Plots => im_str!("plots"),
+        Panel::Console => im_str!("console"),
+    }
+}
+
+/// the window of a panel at its manifest layout, `None` when the panel is disabled
+fn panel_window(
+    config: &UiConfig,
+    panel: Panel,
+    position_condition: imgui::Condition,
+) -> Option<Window<'static>> {
+    if !config.shows(panel) {
+        return None;
+    }
+    let (position, size) = config.layout(panel);
+    Some(
+        Window::new(panel_title(panel))
+            .size(size, imgui::Condition::FirstUseEver)
+            .position(position, position_condition),
+    )
 }
 
 impl ImGuiWrapper {
-    pub fn new(ctx: &mut Context) -> Self {
+    pub fn new(ctx: &mut Context, config: UiConfig) -> Self {
         // Create the imgui object
         let mut imgui = imgui::Context::create();
         let (factory, gfx_device, _, _, _) = graphics::gfx_objects(ctx);
@@ -77,7 +119,7 @@ impl ImGuiWrapper {
             io[Key::Z] = KeyCode::Z as _;
         }
 
-        let font_size = 18f32;
+        let font_size = config.font_size;
         imgui.set_ini_filename(None);
         imgui.fonts().add_font(&[
             FontSource::DefaultFontData {
@@ -87,7 +129,7 @@ impl ImGuiWrapper {
                 }),
             },
             FontSource::TtfData {
-                data: include_bytes!("../resources/font/sarasa-ui-sc-regular.ttf"),
+                data: include_bytes!("../resources/font/mplus-1p-regular.ttf"),
                 size_pixels: font_size,
                 config: Some(FontConfig {
                     rasterizer_multiply: 1.75,
@@ -106,6 +148,13 @@ impl ImGuiWrapper {
             renderer,
             last_frame: Instant::now(),
             mouse_state: MouseState::default(),
+            status_filter: ImString::with_capacity(FILTER_CAPACITY),
+            led_input: LedInput::default(),
+            focus_status: false,
+            repl: Repl::new(),
+            snapshot_name: ImString::with_capacity(FILTER_CAPACITY),
+            timeline: Timeline::new(),
+            config,
         }
     }
 
@@ -129,19 +178,52 @@ impl ImGuiWrapper {
         // Various ui things
         {
             // Window
-            Window::new(im_str!("status"))
-                .size([300.0, 600.0], imgui::Condition::FirstUseEver)
-                .position([50.0, 50.0], imgui::Condition::FirstUseEver)
-                .build(&ui, lua.make_status_render(&ui, ctx));
-
-            Window::new(im_str!("selection"))
-                .size([300.0, 600.0], imgui::Condition::FirstUseEver)
-                .position([350.0, 50.0], imgui::Condition::FirstUseEver)
-                .build(&ui, lua.make_slection_render(&ui));
-            Window::new(im_str!("led"))
-                .size([300.0, 300.0], imgui::Condition::FirstUseEver)
-                .position([600.0, 100.0], imgui::Condition::FirstUseEver)
-                .build(&ui, lua.make_led_render(&ui, LED_CELL_SIZE));
+            let (position_condition, collapsed_condition) = if self.focus_status {
+                (imgui::Condition::Always, imgui::Condition::Always)
+            } else {
+                (imgui::Condition::FirstUseEver, imgui::Condition::Never)
+            };
+            let config = &self.config;
+            let first_use = imgui::Condition::FirstUseEver;
+            if let Some(window) = panel_window(config, Panel::Status, position_condition) {
+                window
+                    .collapsed(false, collapsed_condition)
+                    .focused(self.focus_status)
+                    .build(&ui, lua.make_status_render(&ui, &mut self.status_filter));
+            }
+
+            if let Some(window) = panel_window(config, Panel::Selection, first_use) {
+                window.build(&ui, lua.make_slection_render(&ui));
+            }
+            if let Some(window) = panel_window(config, Panel::Led, first_use) {
+                window.build(&ui, lua.make_led_render(&ui, &mut self.led_input));
+            }
+
+            if let Some(window) = panel_window(config, Panel::Control, first_use) {
+                window.build(&ui, lua.make_control_render(&ui));
+            }
+
+            if let Some(window) = panel_window(config, Panel::Snapshot, first_use) {
+                window.build(&ui, lua.make_snapshot_render(&ui, &mut self.snapshot_name));
+            }
+
+            if let Some(window) = panel_window(config, Panel::Timeline, first_use) {
+                window.build(&ui, lua.make_timeline_render(&ui, &mut self.timeline));
+            }
+
+            if let Some(window) = panel_window(config, Panel::Plots, first_use) {
+                window.build(&ui, lua.make_plots_render(&ui));
+            }
+
+            lua.render_windows(&ui);
+
+            self.focus_status = false;
+            if let Some(window) = panel_window(config, Panel::Console, first_use) {
+                window.build(
+                    &ui,
+                    lua.make_console_render(&ui, &mut self.focus_status, &mut self.repl),
+                );
+            }
         }
 
         // Render
@@ -176,6 +258,14 @@ impl ImGuiWrapper {
         self.mouse_state.wheel_h = 0.0;
     }
 
+    pub fn want_capture_mouse(&self) -> bool {
+        self.imgui.io().want_capture_mouse
+    }
+
+    pub fn want_capture_keyboard(&self) -> bool {
+        self.imgui.io().want_capture_keyboard
+    }
+
     pub fn update_mouse_pos(&mut self, x: f32, y: f32) {
         self.mouse_state.pos = (x as i32, y as i32);
     }
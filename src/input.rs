@@ -0,0 +1,168 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use rlua::{ToLua, Value};
+
+/// stable lower case names for `on_key_down` and `mp.is_key_down`
+pub fn key_name(key: KeyCode) -> &'static str {
+    match key {
+        KeyCode::A => "a",
+        KeyCode::B => "b",
+        KeyCode::C => "c",
+        KeyCode::D => "d",
+        KeyCode::E => "e",
+        KeyCode::F => "f",
+        KeyCode::G => "g",
+        KeyCode::H => "h",
+        KeyCode::I => "i",
+        KeyCode::J => "j",
+        KeyCode::K => "k",
+        KeyCode::L => "l",
+        KeyCode::M => "m",
+        KeyCode::N => "n",
+        KeyCode::O => "o",
+        KeyCode::P => "p",
+        KeyCode::Q => "q",
+        KeyCode::R => "r",
+        KeyCode::S => "s",
+        KeyCode::T => "t",
+        KeyCode::U => "u",
+        KeyCode::V => "v",
+        KeyCode::W => "w",
+        KeyCode::X => "x",
+        KeyCode::Y => "y",
+        KeyCode::Z => "z",
+        KeyCode::Key0 => "0",
+        KeyCode::Key1 => "1",
+        KeyCode::Key2 => "2",
+        KeyCode::Key3 => "3",
+        KeyCode::Key4 => "4",
+        KeyCode::Key5 => "5",
+        KeyCode::Key6 => "6",
+        KeyCode::Key7 => "7",
+        KeyCode::Key8 => "8",
+        KeyCode::Key9 => "9",
+        KeyCode::F1 => "f1",
+        KeyCode::F2 => "f2",
+        KeyCode::F3 => "f3",
+        KeyCode::F4 => "f4",
+        KeyCode::F5 => "f5",
+        KeyCode::F6 => "f6",
+        KeyCode::F7 => "f7",
+        KeyCode::F8 => "f8",
+        KeyCode::F9 => "f9",
+        KeyCode::F10 => "f10",
+        KeyCode::F11 => "f11",
+        KeyCode::F12 => "f12",
+        KeyCode::Left => "left",
+        KeyCode::Right => "right",
+        KeyCode::Up => "up",
+        KeyCode::Down => "down",
+        KeyCode::Space => "space",
+        KeyCode::Return => "enter",
+        KeyCode::NumpadEnter => "enter",
+        KeyCode::Escape => "escape",
+        KeyCode::Tab => "tab",
+        KeyCode::Back => "backspace",
+        KeyCode::Delete => "delete",
+        KeyCode::Insert => "insert",
+        KeyCode::Home => "home",
+        KeyCode::End => "end",
+        KeyCode::PageUp => "pageup",
+        KeyCode::PageDown => "pagedown",
+        KeyCode::LShift => "lshift",
+        KeyCode::RShift => "rshift",
+        KeyCode::LControl => "lctrl",
+        KeyCode::RControl => "rctrl",
+        KeyCode::LAlt => "lalt",
+        KeyCode::RAlt => "ralt",
+        KeyCode::Numpad0 => "kp0",
+        KeyCode::Numpad1 => "kp1",
+        KeyCode::Numpad2 => "kp2",
+        KeyCode::Numpad3 => "kp3",
+        KeyCode::Numpad4 => "kp4",
+        KeyCode::Numpad5 => "kp5",
+        KeyCode::Numpad6 => "kp6",
+        KeyCode::Numpad7 => "kp7",
+        KeyCode::Numpad8 => "kp8",
+        KeyCode::Numpad9 => "kp9",
+        KeyCode::Add => "kp+",
+        KeyCode::Subtract => "kp-",
+        KeyCode::Multiply => "kp*",
+        KeyCode::Divide => "kp/",
+        KeyCode::Decimal => "kp.",
+        KeyCode::Minus => "-",
+        KeyCode::Equals => "=",
+        KeyCode::Comma => ",",
+        KeyCode::Period => ".",
+        KeyCode::Slash => "/",
+        KeyCode::Backslash => "\\",
+        KeyCode::Semicolon => ";",
+        KeyCode::Apostrophe => "'",
+        KeyCode::Grave => "`",
+        KeyCode::LBracket => "[",
+        KeyCode::RBracket => "]",
+        _ => "unknown",
+    }
+}
+
+pub fn mouse_button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Right => "right",
+        MouseButton::Middle => "middle",
+        MouseButton::Other(_) => "other",
+    }
+}
+
+/// passed to lua as `{ shift = bool, ctrl = bool, alt = bool, logo = bool }`
+pub struct LuaKeyMods(pub KeyMods);
+
+impl<'lua> ToLua<'lua> for LuaKeyMods {
+    fn to_lua(self, lua_ctx: rlua::Context<'lua>) -> rlua::Result<Value<'lua>> {
+        let mods = lua_ctx.create_table()?;
+        mods.set("shift", self.0.contains(KeyMods::SHIFT))?;
+        mods.set("ctrl", self.0.contains(KeyMods::CTRL))?;
+        mods.set("alt", self.0.contains(KeyMods::ALT))?;
+        mods.set("logo", self.0.contains(KeyMods::LOGO))?;
+        Ok(Value::Table(mods))
+    }
+}
+
+/// an input event as it reached lua, kept so it can be recorded and replayed.
+/// `captured` is set when imgui had the mouse or keyboard, then only the
+/// `mp.is_*_down` state changes and no hook is called
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    MouseMove {
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+        captured: bool,
+    },
+    MouseDown {
+        button: String,
+        x: f32,
+        y: f32,
+    },
+    MouseUp {
+        button: String,
+        x: f32,
+        y: f32,
+        captured: bool,
+    },
+    KeyDown {
+        key: String,
+        mods: KeyMods,
+        repeat: bool,
+    },
+    KeyUp {
+        key: String,
+        mods: KeyMods,
+        captured: bool,
+    },
+    Text(String),
+    Wheel {
+        x: f32,
+        y: f32,
+    },
+}
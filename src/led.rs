@@ -0,0 +1,305 @@
+use imgui::{im_str, MouseButton};
+use rlua::{Integer, Table, Value};
+
+use crate::show::read_color;
+
+const DEFAULT_SIZE: usize = 16;
+const DEFAULT_GAP: f32 = 4.0;
+/// a grid past this many cells is an error rather than a frozen frame
+const MAX_CELLS: usize = 256 * 256;
+const ON_COLOR: [f32; 4] = [0.5, 1.0, 1.0, 1.0];
+const OFF_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 1.0];
+const LED_BUTTONS: [(MouseButton, &str); 3] = [
+    (MouseButton::Left, "left"),
+    (MouseButton::Right, "right"),
+    (MouseButton::Middle, "middle"),
+];
+
+/// the `&'static` name of a button, for events read back from a recording
+pub fn led_button(name: &str) -> Option<&'static str> {
+    LED_BUTTONS
+        .iter()
+        .map(|(_, button)| *button)
+        .find(|button| *button == name)
+}
+
+fn read_rgba(table: &Table) -> rlua::Result<[f32; 4]> {
+    Ok(read_color(table)?.into())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LedEvent {
+    Click {
+        x: usize,
+        y: usize,
+        button: &'static str,
+    },
+    Hover {
+        x: usize,
+        y: usize,
+    },
+}
+
+/// mouse state of the led window that has to outlive a frame
+#[derive(Default)]
+pub struct LedInput {
+    hovered: Option<(usize, usize)>,
+    /// the held button and the last cell it painted, `None` after the mouse left the grid
+    painting: Option<(MouseButton, Option<(usize, usize)>)>,
+}
+
+pub struct Led {
+    pub width: usize,
+    pub height: usize,
+    /// row major, `cells[y * width + x]`
+    pub cells: Vec<[f32; 4]>,
+    /// fit the window when not set
+    pub cell_size: Option<f32>,
+    pub gap: f32,
+}
+
+impl Default for Led {
+    fn default() -> Self {
+        Led {
+            width: DEFAULT_SIZE,
+            height: DEFAULT_SIZE,
+            cells: vec![OFF_COLOR; DEFAULT_SIZE * DEFAULT_SIZE],
+            cell_size: None,
+            gap: DEFAULT_GAP,
+        }
+    }
+}
+
+impl Led {
+    /// `mp_led` is either `{ width, height, cells, palette, ... }`, where `cells` is a
+    /// lua sequence, `cells[y * width + x + 1]` with `x` and `y` from 0 as the callbacks
+    /// get them. the old format is the cells table itself, indexed from 0 in a 16x16 grid.
+    /// a cell is a bool, a palette index starting at 1, or a color table
+    pub fn build(&mut self, lua_table: Table) -> rlua::Result<()> {
+        let cells = lua_table.get::<_, Option<Table>>("cells")?;
+        let is_old_format = cells.is_none()
+            && lua_table.get::<_, Option<usize>>("width")?.is_none()
+            && lua_table.get::<_, Option<usize>>("height")?.is_none();
+        let (cells, first_index) = if is_old_format {
+            (Some(lua_table.clone()), 0)
+        } else {
+            self.width = lua_table
+                .get::<_, Option<usize>>("width")?
+                .unwrap_or(DEFAULT_SIZE);
+            self.height = lua_table
+                .get::<_, Option<usize>>("height")?
+                .unwrap_or(DEFAULT_SIZE);
+            self.cell_size = lua_table.get("cell_size")?;
+            self.gap = lua_table
+                .get::<_, Option<f32>>("gap")?
+                .unwrap_or(DEFAULT_GAP);
+            (cells, 1)
+        };
+        let too_large = self
+            .width
+            .checked_mul(self.height)
+            .map_or(true, |count| count > MAX_CELLS);
+        if too_large {
+            return Err(rlua::Error::RuntimeError(format!(
+                "mp_led: {}x{} is more than {} cells",
+                self.width, self.height, MAX_CELLS
+            )));
+        }
+
+        let mut palette = vec![];
+        if let Some(palette_table) = lua_table.get::<_, Option<Table>>("palette")? {
+            for color in palette_table.sequence_values::<Table>() {
+                palette.push(read_rgba(&color?)?);
+            }
+        }
+        let on_color = match lua_table.get::<_, Option<Table>>("on_color")? {
+            Some(color) => read_rgba(&color)?,
+            None => ON_COLOR,
+        };
+        let off_color = match lua_table.get::<_, Option<Table>>("off_color")? {
+            Some(color) => read_rgba(&color)?,
+            None => OFF_COLOR,
+        };
+
+        self.cells = vec![off_color; self.width * self.height];
+        let cells = match cells {
+            Some(cells) => cells,
+            None => return Ok(()),
+        };
+        for pair in cells.pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            let index = match key {
+                Value::Integer(i) if i >= first_index => (i - first_index) as usize,
+                _ => continue,
+            };
+            if index >= self.cells.len() {
+                continue;
+            }
+            self.cells[index] = match value {
+                Value::Boolean(true) => on_color,
+                Value::Integer(i) => palette_color(&palette, i).unwrap_or(off_color),
+                Value::Number(n) => palette_color(&palette, n as Integer).unwrap_or(off_color),
+                Value::Table(color) => read_rgba(&color)?,
+                _ => off_color,
+            };
+        }
+        Ok(())
+    }
+
+    /// draw the cells and hit test the mouse against them.
+    /// holding a button and moving across cells clicks every cell on the way
+    pub fn render(&self, ui: &imgui::Ui, input: &mut LedInput) -> Vec<LedEvent> {
+        let mut events = vec![];
+        if self.width == 0 || self.height == 0 {
+            return events;
+        }
+        let origin = ui.cursor_screen_pos();
+        let avail = ui.content_region_avail();
+        // square cells, so the grid keeps its aspect ratio
+        let cell_size = self.cell_size.unwrap_or_else(|| {
+            (avail[0] / self.width as f32)
+                .min(avail[1] / self.height as f32)
+                .max(1.0)
+        });
+        let gap = self.gap.min(cell_size - 1.0).max(0.0);
+
+        {
+            let draw_list = ui.get_window_draw_list();
+            for (i, color) in self.cells.iter().enumerate() {
+                let x = (i % self.width) as f32;
+                let y = (i / self.width) as f32;
+                let x1 = origin[0] + x * cell_size;
+                let y1 = origin[1] + y * cell_size;
+                draw_list
+                    .add_rect(
+                        [x1, y1],
+                        [x1 + cell_size - gap, y1 + cell_size - gap],
+                        *color,
+                    )
+                    .filled(true)
+                    .build();
+            }
+        }
+        // an invisible button keeps imgui from dragging the window while painting
+        ui.invisible_button(
+            im_str!("##led"),
+            [
+                cell_size * self.width as f32,
+                cell_size * self.height as f32,
+            ],
+        );
+
+        let hovered_cell = if ui.is_item_hovered() {
+            let mouse = ui.io().mouse_pos;
+            let x = ((mouse[0] - origin[0]) / cell_size).floor();
+            let y = ((mouse[1] - origin[1]) / cell_size).floor();
+            if x >= 0.0 && y >= 0.0 && (x as usize) < self.width && (y as usize) < self.height {
+                Some((x as usize, y as usize))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+        if hovered_cell != input.hovered {
+            if let Some((x, y)) = hovered_cell {
+                events.push(LedEvent::Hover { x, y });
+            }
+            input.hovered = hovered_cell;
+        }
+
+        if let Some((button, last_cell)) = &mut input.painting {
+            if !ui.is_mouse_down(*button) {
+                input.painting = None;
+            } else if hovered_cell.is_none() {
+                // coming back in paints from where the mouse enters, not from where it left
+                *last_cell = None;
+            }
+        }
+        if let Some(cell) = hovered_cell {
+            for (button, name) in LED_BUTTONS.iter() {
+                let clicked = if ui.is_mouse_clicked(*button) {
+                    vec![cell]
+                } else {
+                    match input.painting {
+                        // a fast drag crosses several cells in one frame, the
+                        // last cell was already clicked
+                        Some((painting_button, Some(last_cell)))
+                            if painting_button == *button && last_cell != cell =>
+                        {
+                            cell_line(last_cell, cell).into_iter().skip(1).collect()
+                        }
+                        Some((painting_button, None)) if painting_button == *button => vec![cell],
+                        _ => vec![],
+                    }
+                };
+                for (x, y) in clicked {
+                    events.push(LedEvent::Click {
+                        x,
+                        y,
+                        button: *name,
+                    });
+                    input.painting = Some((*button, Some(cell)));
+                }
+            }
+        }
+        events
+    }
+}
+
+/// the cells from `from` to `to`, both included. each step moves along one axis only,
+/// so every painted cell shares an edge with the one before it
+fn cell_line(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let dx = (to.0 as i64 - x).abs();
+    let dy = (to.1 as i64 - y).abs();
+    let step_x = if x < to.0 as i64 { 1 } else { -1 };
+    let step_y = if y < to.1 as i64 { 1 } else { -1 };
+    let mut cells = vec![(x as usize, y as usize)];
+    let (mut moved_x, mut moved_y) = (0, 0);
+    while moved_x < dx || moved_y < dy {
+        // step x while the line's next x crossing comes before its next y crossing
+        if (1 + 2 * moved_x) * dy < (1 + 2 * moved_y) * dx {
+            x += step_x;
+            moved_x += 1;
+        } else {
+            y += step_y;
+            moved_y += 1;
+        }
+        cells.push((x as usize, y as usize));
+    }
+    cells
+}
+
+fn palette_color(palette: &[[f32; 4]], index: Integer) -> Option<[f32; 4]> {
+    if index < 1 {
+        return None;
+    }
+    palette.get(index as usize - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_line_includes_both_ends() {
+        assert_eq!(cell_line((1, 1), (1, 1)), vec![(1, 1)]);
+        assert_eq!(cell_line((2, 2), (2, 0)), vec![(2, 2), (2, 1), (2, 0)]);
+    }
+
+    #[test]
+    fn cell_line_has_no_gaps() {
+        assert_eq!(
+            cell_line((0, 0), (3, 1)),
+            vec![(0, 0), (1, 0), (1, 1), (2, 1), (3, 1)]
+        );
+        let cells = cell_line((3, 3), (0, 0));
+        assert_eq!(cells.len(), 7);
+        for pair in cells.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let distance = (a.0 as i64 - b.0 as i64).abs() + (a.1 as i64 - b.1 as i64).abs();
+            assert_eq!(distance, 1);
+        }
+    }
+}
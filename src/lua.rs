@@ -1,154 +1,185 @@
 use path_slash::PathBufExt;
-use rlua::{Function, Integer, Lua, Table, Value};
+use rlua::{FromLua, Function, Lua, MultiValue, RegistryKey, Table, ToLua, ToLuaMulti, Value};
 use std::error::Error;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::rc::Rc;
 
-use ggez::Context;
+use ggez::event::KeyCode;
 use imgui::{im_str, ImString};
 
-use crate::signal::{SIGNAL_RELOAD_SELECTION, SIGNAL_TABLE};
+use crate::clock::Clock;
+use crate::console::{error_text, Console, ConsoleLevel};
+use crate::dump::{dump_value, DumpFormat};
+use crate::history::{History, Timeline, DEFAULT_HISTORY_LENGTH};
+use crate::host::{create_mp_table, Command, HostCommand};
+use crate::input::{InputEvent, LuaKeyMods};
+use crate::led::{Led, LedEvent, LedInput};
+use crate::plot::{sample_declared, Plots};
+use crate::project::Project;
+use crate::record::{RecordEvent, Recorder};
+use crate::repl::Repl;
+use crate::rng::Rng;
+use crate::selection::{
+    item_identity, read_props, same_signature, write_signature, ItemPath, ItemProps, UiSelection,
+};
+use crate::shared::Shared;
+use crate::show::Show;
+use crate::snapshot::{capture_state, restore_state, skipped_message, Snapshots, StateValue};
+use crate::state::{get_state_path, parse_state_path, set_state_path, StateKey, STATE_TABLE};
+use crate::status::{display_value, pretty_value, UiStatus};
+use crate::watcher::FileWatcher;
+use crate::windows::UiWindows;
 
-#[derive(Debug)]
-enum UiStatusItem {
-    Text(ImString),
-}
+const RELOAD_HOOK: &str = "on_reload";
+const TEST_PREFIX: &str = "test_";
+const TEST_TABLE: &str = "mp_tests";
+const INPUT_TABLE: &str = "__mp_input";
+const CONFIG_TABLE: &str = "mp_config";
+const PLOTS_TABLE: &str = "mp_plots";
+const DEFAULT_TICK_RATE: u32 = 60;
+const REPL_CHUNK: &str = "=repl";
+const REPL_DEPTH: usize = 4;
 
-pub fn log_lua_result(result: &rlua::Result<()>) {
-    if let Err(e) = result {
-        println!("[LuaError]{}", e);
+/// a table field of an item, given as the table or as the name of a global,
+/// e.g. `led = "mp_led"` shares the led window's cells
+fn item_table_field<'lua>(
+    lua_ctx: rlua::Context<'lua>,
+    path: &ItemPath,
+    field: &str,
+) -> rlua::Result<Option<Table<'lua>>> {
+    match path.table(lua_ctx)?.get::<_, Value>(field)? {
+        Value::Table(table) => Ok(Some(table)),
+        Value::String(name) => lua_ctx.globals().get(name),
+        _ => Ok(None),
     }
 }
 
-struct UiStatus {
-    items: Vec<UiStatusItem>,
+/// `@` tells lua the chunk is a file, so errors read `path/to/file.lua:12:`
+fn chunk_name(path: &Path) -> String {
+    format!("@{}", path.display())
 }
 
-fn display_value(value: &Value) -> String {
-    match value {
-        Value::Table(_) => String::from("table"),
-        Value::String(str) => String::from(str.to_str().unwrap_or("")),
-        Value::Boolean(b) => format!("{}", b),
-        Value::Nil => String::from("Nil"),
-        Value::Number(n) => format!("{}", n),
-        Value::Integer(n) => format!("{}", n),
-        _ => format!("{:?}", value),
+/// every table `mp_selection` and `mp_windows` are built from, see `write_signature`
+fn ui_signature(lua_ctx: rlua::Context) -> rlua::Result<Vec<Value>> {
+    let globals = lua_ctx.globals();
+    let mut signature = vec![];
+    if let Some(mp_selection) = globals.get::<_, Option<Table>>("mp_selection")? {
+        write_signature(&mp_selection, &mut signature)?;
     }
-}
-
-impl UiStatus {
-    pub fn build(&mut self, lua_table: Table, depth: u32) -> rlua::Result<()> {
-        for pair in lua_table.pairs::<Value, Value>() {
-            let (key, value) = pair?;
-            let indent = " ".repeat((depth * 2) as usize);
-            match value {
-                Value::Table(inner_table) => {
-                    self.items.push(UiStatusItem::Text(im_str!(
-                        "{}{}: ",
-                        indent,
-                        display_value(&key)
-                    )));
-                    self.build(inner_table, depth + 1)?;
-                }
-                _ => {
-                    // println!("value = {}", display_value(&value));
-                    self.items.push(UiStatusItem::Text(im_str!(
-                        "{}{}: {}",
-                        indent,
-                        display_value(&key),
-                        display_value(&value)
-                    )));
+    signature.push(Value::Boolean(false));
+    if let Some(mp_windows) = globals.get::<_, Option<Table>>("mp_windows")? {
+        signature.push(Value::Table(mp_windows.clone()));
+        for window in mp_windows.sequence_values::<Value>() {
+            let window = window?;
+            if let Value::Table(window) = &window {
+                if let Some(content) = window.get::<_, Option<Table>>("content")? {
+                    write_signature(&content, &mut signature)?;
                 }
             }
+            signature.push(window);
         }
-        Ok(())
-    }
-}
-
-enum UiSelectionItem {
-    Button { index: usize, text: String },
-}
-
-struct UiSelection {
-    items: Vec<UiSelectionItem>,
-}
-
-impl UiSelection {
-    pub fn build(&mut self, lua_table: Table) -> rlua::Result<()> {
-        for pair in lua_table.pairs::<Integer, Table>() {
-            let (index, selection_table) = pair?;
-            let text = selection_table.get::<_, String>("text")?;
-            self.items.push(UiSelectionItem::Button {
-                index: index as usize,
-                text,
-            })
-        }
-        Ok(())
-    }
-}
-
-const LED_SIZE: usize = 16;
-
-pub struct Led {
-    pub buf: [bool; LED_SIZE * LED_SIZE],
-}
-
-impl Default for Led {
-    fn default() -> Self {
-        Led {
-            buf: [false; LED_SIZE * LED_SIZE],
-        }
-    }
-}
-
-impl Led {
-    pub fn build(&mut self, lua_table: Table) -> rlua::Result<()> {
-        for pair in lua_table.pairs::<Integer, bool>() {
-            let (index, is_on) = pair?;
-            if index >= 0 && (index as usize) < self.buf.len() {
-                self.buf[index as usize] = is_on;
-            }
-        }
-        Ok(())
     }
+    Ok(signature)
 }
 
 pub struct MpLua {
     lua: Lua,
-    entry_file: PathBuf,
+    project: Project,
+    /// the entry file as it was last loaded, a recording starts from it
+    source: String,
     selections: Option<Rc<UiSelection>>,
+    windows: Option<Rc<UiWindows>>,
+    /// the tables `selections` and `windows` were built from, see `refresh_ui_selection`.
+    /// `None` until a build succeeds
+    ui_signature: Option<RegistryKey>,
+    watcher: FileWatcher,
+    reload_error: Option<String>,
+    /// errors passed to `report_error` since the last `check_errors`
+    errors: Cell<usize>,
+    commands: Shared<Vec<Command>>,
+    console: RefCell<Console>,
+    clock: RefCell<Clock>,
+    snapshots: Shared<Snapshots>,
+    history: RefCell<History>,
+    recorder: RefCell<Option<Recorder>>,
+    /// set while a recording plays, the ui and file changes can't change the run then
+    replaying: bool,
+    /// the seed the run started with, `mp.seed` may change the current one
+    seed: u64,
+    rng: Shared<Rng>,
+    plots: Shared<Plots>,
 }
 
 impl MpLua {
-    pub fn new(entry_file: String) -> Self {
+    pub fn new(project: Project) -> Result<Self, Box<dyn Error>> {
+        MpLua::with_seed(project, Rng::time_seed())
+    }
+
+    /// the seed is set before the script is loaded, so top level `math.random` is covered.
+    /// a script that fails to load is an error, not a panic, so `--headless` exits cleanly
+    pub fn with_seed(project: Project, seed: u64) -> Result<Self, Box<dyn Error>> {
+        let source = fs::read_to_string(&project.entry_file)?;
+        MpLua::with_source(project, seed, source)
+    }
+
+    /// load `source` as the entry file instead of reading it, modules still come from disk
+    pub fn with_source(
+        project: Project,
+        seed: u64,
+        source: String,
+    ) -> Result<Self, Box<dyn Error>> {
         let lua = Lua::new();
-        let path = PathBuf::from(entry_file);
+        let project_dir = project.root.clone();
         let mut mp_lua = MpLua {
             lua,
-            entry_file: path,
+            project,
+            source,
             selections: None,
+            windows: None,
+            ui_signature: None,
+            watcher: FileWatcher::new(),
+            reload_error: None,
+            errors: Cell::new(0),
+            commands: Shared::default(),
+            console: RefCell::new(Console::new()),
+            clock: RefCell::new(Clock::new()),
+            plots: Shared::new(Plots::new(&project_dir)),
+            snapshots: Shared::new(Snapshots::new(project_dir)),
+            history: RefCell::new(History::new()),
+            recorder: RefCell::new(None),
+            replaying: false,
+            seed,
+            rng: Shared::new(Rng::new(seed)),
         };
-        mp_lua.add_require_path().unwrap();
-        if let Err(e) = mp_lua.load() {
-            panic!("{}", e);
-        }
-        mp_lua
+        mp_lua.add_require_path()?;
+        // scripts may use `mp` at load time, e.g. `mp.EShow` inside `mp_show`
+        mp_lua.inject_functions()?;
+        mp_lua.load()?;
+        mp_lua.watch_files();
+        Ok(mp_lua)
     }
 
+    /// the ui is built after `awake`, so items it adds to `mp_selection` show up
     pub fn awake(&mut self) -> rlua::Result<()> {
-        self.load_ui_selection()?;
-        self.clear_signals()?;
-        self.inject_functions()?;
         self.lua.load_from_std_lib(rlua::StdLib::STRING)?;
         self.run_awake()?;
+        self.load_ui_selection()?;
         Ok(())
     }
 
     fn inject_functions(&mut self) -> rlua::Result<()> {
-        let mp_lib = std::include_bytes!("../resources/lua/signal.lua");
+        let mp_lib = std::include_bytes!("../resources/lua/mp.lua");
+        let commands = self.commands.clone();
+        let snapshots = self.snapshots.clone();
+        let rng = self.rng.clone();
+        let plots = self.plots.clone();
         self.lua.context(|lua_ctx| {
+            let mp = create_mp_table(lua_ctx, &commands, &snapshots, &rng, &plots)?;
+            lua_ctx.globals().set("mp", mp)?;
             lua_ctx
                 .load(&String::from_utf8_lossy(mp_lib).into_owned())
                 .exec()?;
@@ -157,47 +188,99 @@ impl MpLua {
         Ok(())
     }
 
-    pub fn tick_signal(&mut self) -> rlua::Result<()> {
-        let mut signals = vec![];
-        self.lua.context(|lua_ctx| {
-            let globals = lua_ctx.globals();
-            let lua_signals = globals.get::<_, Table>(SIGNAL_TABLE)?;
-            for pair in lua_signals.pairs::<Value, Value>() {
-                let (_, signal) = pair?;
-                if let Value::Integer(s) = signal {
-                    signals.push(s);
+    /// run the commands queued by `mp` functions since the last tick.
+    /// the ones that need the window are returned to the caller. a failing
+    /// command is reported and the ones after it still run
+    pub fn tick_commands(&mut self) -> rlua::Result<Vec<HostCommand>> {
+        let mut host_commands = vec![];
+        let commands = self.commands.lock().drain(..).collect::<Vec<_>>();
+        if !commands.is_empty() {
+            self.record(RecordEvent::Commands);
+        }
+        // commands queued while running these are kept for the next tick
+        for command in commands {
+            let result = match command {
+                Command::ReloadSelection => self.load_ui_selection(),
+                Command::Select(index) => self.run_selection(&ItemPath::selection(index)),
+                Command::Log(text) => {
+                    println!("[LuaLog]{}", text);
+                    self.console.borrow_mut().push(ConsoleLevel::Log, text);
+                    Ok(())
                 }
-            }
-            Ok(())
-        })?;
-        for signal in signals {
-            self.run_signal(signal)?;
+                Command::Error(text) => {
+                    println!("[Error]{}", text);
+                    self.console.borrow_mut().push(ConsoleLevel::Error, text);
+                    Ok(())
+                }
+                Command::Host(host_command) => {
+                    host_commands.push(host_command);
+                    Ok(())
+                }
+            };
+            self.log_result(&result);
         }
-        self.clear_signals()?;
-        Ok(())
+        Ok(host_commands)
     }
 
-    fn clear_signals(&mut self) -> rlua::Result<()> {
-        self.lua.context(|lua_ctx| {
-            lua_ctx.load(&format!("{} = {{}}", SIGNAL_TABLE)).exec()?;
-            Ok(())
+    /// `mp_selection` and every window of `mp_windows` are built on their own, one
+    /// that fails is reported and left out instead of taking the others down
+    fn load_ui_selection(&mut self) -> rlua::Result<()> {
+        let signature = self.lua.context(|lua_ctx| {
+            let signature = lua_ctx.create_sequence_from(ui_signature(lua_ctx)?)?;
+            lua_ctx.create_registry_value(signature)
         })?;
-        Ok(())
-    }
-
-    fn run_signal(&mut self, signal: i64) -> rlua::Result<()> {
-        match signal {
-            SIGNAL_RELOAD_SELECTION => {
-                self.load_ui_selection()?;
+        let mut errors = vec![];
+        self.selections = match self.build_ui_selection() {
+            Ok(selections) => Some(Rc::new(selections)),
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        };
+        self.windows = match self.build_ui_windows() {
+            Ok((windows, window_errors)) => {
+                errors.extend(window_errors);
+                Some(Rc::new(windows))
             }
-            _ => {}
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        };
+        for e in &errors {
+            self.report_error(e);
+        }
+        // without a signature the build is tried again next frame, the script
+        // may fix a bad field without replacing the table
+        let built = if errors.is_empty() {
+            Some(signature)
+        } else {
+            self.lua
+                .context(|lua_ctx| lua_ctx.remove_registry_value(signature))?;
+            None
         };
+        if let Some(old) = std::mem::replace(&mut self.ui_signature, built) {
+            self.lua
+                .context(|lua_ctx| lua_ctx.remove_registry_value(old))?;
+        }
         Ok(())
     }
 
-    fn load_ui_selection(&mut self) -> rlua::Result<()> {
-        let selections = self.build_ui_selection()?;
-        self.selections.replace(Rc::new(selections));
+    /// rebuild `mp_selection` and `mp_windows` when their tables were replaced, added
+    /// or removed since the last build, called every frame so scripts don't have to
+    /// call `mp.reload_selection`. the built items keep their state otherwise
+    pub fn refresh_ui_selection(&mut self) -> rlua::Result<()> {
+        let changed = self.lua.context(|lua_ctx| -> rlua::Result<bool> {
+            let built = match &self.ui_signature {
+                Some(key) => lua_ctx.registry_value::<Table>(key)?,
+                None => return Ok(true),
+            };
+            let same = same_signature(lua_ctx, &built, ui_signature(lua_ctx)?)?;
+            Ok(!same)
+        })?;
+        if changed {
+            self.load_ui_selection()?;
+        }
         Ok(())
     }
 
@@ -212,34 +295,493 @@ impl MpLua {
     }
 
     fn load(&mut self) -> Result<(), Box<dyn Error>> {
-        let file_content = fs::read_to_string(&self.entry_file)?;
+        let name = chunk_name(&self.project.entry_file);
+        let source = &self.source;
         self.lua
-            .context(|lua_ctx| lua_ctx.load(&file_content).exec())?;
+            .context(|lua_ctx| lua_ctx.load(source).set_name(&name)?.exec())?;
+        Ok(())
+    }
+
+    /// print the error and keep it for the console window
+    pub fn report_error(&self, error: &rlua::Error) {
+        let text = error_text(error);
+        println!("[LuaError]{}", text);
+        self.console.borrow_mut().push(ConsoleLevel::Error, text);
+        self.errors.set(self.errors.get() + 1);
+    }
+
+    /// an error when anything was reported since the last call. a windowed run keeps
+    /// going after an error, headless runs and tests use this to fail on them
+    pub fn check_errors(&self) -> Result<(), Box<dyn Error>> {
+        match self.errors.replace(0) {
+            0 => Ok(()),
+            1 => Err(From::from("a lua error was reported, see above")),
+            count => Err(From::from(format!(
+                "{} lua errors were reported, see above",
+                count
+            ))),
+        }
+    }
+
+    pub fn log_result(&self, result: &rlua::Result<()>) {
+        if let Err(e) = result {
+            self.report_error(e);
+        }
+    }
+
+    /// execute an extra file in this state, e.g. a `*_test.lua` next to the entry file
+    pub fn load_file(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let file_content = fs::read_to_string(path)?;
+        self.lua.context(|lua_ctx| {
+            lua_ctx
+                .load(&file_content)
+                .set_name(&chunk_name(path))?
+                .exec()
+        })?;
         Ok(())
     }
 
+    /// global `test_*` functions and the entries of `mp_tests`, sorted by name
+    pub fn test_names(&self) -> rlua::Result<Vec<String>> {
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            let mut names = vec![];
+            for pair in globals.clone().pairs::<Value, Value>() {
+                if let (Value::String(name), Value::Function(_)) = pair? {
+                    let name = name.to_str()?;
+                    if name.starts_with(TEST_PREFIX) {
+                        names.push(String::from(name));
+                    }
+                }
+            }
+            if let Some(tests) = globals.get::<_, Option<Table>>(TEST_TABLE)? {
+                for pair in tests.pairs::<String, Function>() {
+                    let (name, _) = pair?;
+                    names.push(format!("{}.{}", TEST_TABLE, name));
+                }
+            }
+            names.sort();
+            Ok(names)
+        })
+    }
+
+    pub fn run_test(&self, name: &str) -> rlua::Result<()> {
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            let table_prefix = format!("{}.", TEST_TABLE);
+            let func = if let Some(test_name) = name.strip_prefix(&table_prefix) {
+                globals
+                    .get::<_, Table>(TEST_TABLE)?
+                    .get::<_, Function>(test_name)?
+            } else {
+                globals.get::<_, Function>(name)?
+            };
+            func.call::<_, ()>(())
+        })
+    }
+
+    /// paths of the modules pulled in by `require` that live in `package.path`
+    fn required_files(&self) -> rlua::Result<Vec<(String, PathBuf)>> {
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            let package = globals.get::<_, Table>("package")?;
+            let search_path = package.get::<_, Function>("searchpath")?;
+            let lua_path = package.get::<_, String>("path")?;
+            let loaded = package.get::<_, Table>("loaded")?;
+            let mut files = vec![];
+            for pair in loaded.pairs::<String, Value>() {
+                let (name, _) = pair?;
+                if let Some(file) =
+                    search_path.call::<_, Option<String>>((name.as_str(), lua_path.as_str()))?
+                {
+                    files.push((name, PathBuf::from(file)));
+                }
+            }
+            Ok(files)
+        })
+    }
+
+    fn watch_files(&mut self) {
+        self.watcher.clear();
+        self.watcher.watch(self.project.entry_file.clone());
+        match self.required_files() {
+            Ok(files) => {
+                for (_, file) in files {
+                    self.watcher.watch(file);
+                }
+            }
+            Err(e) => self.report_error(&e),
+        }
+    }
+
+    pub fn tick_reload(&mut self) {
+        // a replay reloads the recorded source instead
+        if !self.watcher.poll() || self.replaying {
+            return;
+        }
+        self.console_log(format!("reloading {}", self.project.entry_file.display()));
+        let result = match fs::read_to_string(&self.project.entry_file) {
+            Ok(source) => {
+                self.record(RecordEvent::Reload(source.clone()));
+                self.reload(&source)
+            }
+            Err(e) => Err(From::from(e)),
+        };
+        self.finish_reload(result);
+    }
+
+    fn finish_reload(&mut self, result: Result<(), Box<dyn Error>>) {
+        self.reload_error = match result {
+            Ok(_) => None,
+            Err(e) => {
+                let text = format!("reload failed: {}", e);
+                println!("[ReloadError]{}", text);
+                self.console
+                    .borrow_mut()
+                    .push(ConsoleLevel::Error, text.clone());
+                Some(text)
+            }
+        };
+        // files may have been added or removed by the new `require`s
+        self.watch_files();
+    }
+
+    /// re-execute the entry file and its modules, keeping `mp_state` when possible.
+    /// if the script defines `on_reload(old_state)` it decides what happens to
+    /// the state, otherwise `awake` runs again and the old state is restored
+    fn reload(&mut self, source: &str) -> Result<(), Box<dyn Error>> {
+        let modules = self.required_files()?;
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            let old_state = globals.get::<_, Value>("mp_state")?;
+            let loaded = globals
+                .get::<_, Table>("package")?
+                .get::<_, Table>("loaded")?;
+            for (name, _) in modules {
+                loaded.set(name, Value::Nil)?;
+            }
+            globals.set(RELOAD_HOOK, Value::Nil)?;
+
+            let result = (|| -> rlua::Result<()> {
+                lua_ctx
+                    .load(source)
+                    .set_name(&chunk_name(&self.project.entry_file))?
+                    .exec()?;
+                match globals.get::<_, Option<Function>>(RELOAD_HOOK)? {
+                    Some(on_reload) => on_reload.call::<_, ()>(old_state.clone()),
+                    None => globals.get::<_, Function>("awake")?.call::<_, ()>(()),
+                }
+            })();
+            // a failed reload keeps running on the state it had, whatever the new script assigned
+            let keep_old_state =
+                result.is_err() || globals.get::<_, Option<Function>>(RELOAD_HOOK)?.is_none();
+            if keep_old_state {
+                if let Value::Table(_) = old_state {
+                    globals.set("mp_state", old_state)?;
+                }
+            }
+            result
+        })?;
+        self.source = String::from(source);
+        self.load_ui_selection()?;
+        Ok(())
+    }
+
+    /// modules are found from the project root, `require "state"` loads `<root>/state.lua`
     fn add_require_path(&mut self) -> Result<(), Box<dyn Error>> {
-        let mut path = self.entry_file.clone();
-        path.pop();
-        let lua_path_base = path.to_slash().unwrap();
-        let lua = format!(
-            r#"package.path = package.path..';'..'./{}/?.lua' print(package.path)"#,
-            lua_path_base
-        );
-        self.lua.context(|lua_ctx| lua_ctx.load(&lua).exec())?;
+        let root = self.project.root.to_slash().unwrap();
+        self.lua.context(|lua_ctx| {
+            let package = lua_ctx.globals().get::<_, Table>("package")?;
+            let path = package.get::<_, String>("path")?;
+            package.set("path", format!("{0}/?.lua;{0}/?/init.lua;{1}", root, path))
+        })?;
         Ok(())
     }
 
-    fn build_ui_status(&self, ctx: &Context) -> rlua::Result<UiStatus> {
-        let mut info = UiStatus { items: vec![] };
+    pub fn update(&self, delta: f64, time_since_start: f64) -> rlua::Result<()> {
+        self.plots.lock().time = time_since_start;
         self.lua.context(|lua_ctx| {
             let globals = lua_ctx.globals();
             let func_update = globals.get::<_, Function>("update")?;
-            let delta = ggez::timer::delta(&ctx).as_secs_f64();
-            let time_since_start = ggez::timer::time_since_start(&ctx).as_secs_f64();
-            func_update.call::<_, ()>((delta, time_since_start))?;
-            let state = globals.get::<_, Table>("mp_state")?;
-            info.build(state, 0u32)?;
+            func_update.call::<_, ()>((delta, time_since_start))
+        })?;
+        // a bad `mp_plots` entry shouldn't stop the simulation or its history
+        self.log_result(&self.sample_plots());
+        Ok(())
+    }
+
+    /// push this tick's value of every series declared in `mp_plots`
+    fn sample_plots(&self) -> rlua::Result<()> {
+        let samples = self.lua.context(|lua_ctx| {
+            match lua_ctx.globals().get::<_, Option<Table>>(PLOTS_TABLE)? {
+                Some(mp_plots) => sample_declared(lua_ctx, mp_plots),
+                None => Ok(vec![]),
+            }
+        })?;
+        let mut plots = self.plots.lock();
+        for sample in samples {
+            plots.configure(&sample.name, sample.config);
+            if let Some(value) = sample.value {
+                plots.push(&sample.name, value);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn project(&self) -> &Project {
+        &self.project
+    }
+
+    /// `mp_config.tick_rate`, how many times per second `update` is called.
+    /// falls back to `runtime.tick_rate` of the manifest
+    pub fn tick_rate(&self) -> u32 {
+        let tick_rate = self.lua.context(|lua_ctx| -> rlua::Result<Option<u32>> {
+            match lua_ctx.globals().get::<_, Option<Table>>(CONFIG_TABLE)? {
+                Some(config) => config.get("tick_rate"),
+                None => Ok(None),
+            }
+        });
+        match (tick_rate, self.project.manifest.runtime.tick_rate) {
+            (Ok(Some(rate)), _) if rate > 0 => rate,
+            (_, Some(rate)) if rate > 0 => rate,
+            _ => DEFAULT_TICK_RATE,
+        }
+    }
+
+    /// `mp_config.history_length`, how many frames the timeline keeps.
+    /// falls back to `runtime.history_length` of the manifest
+    pub fn history_length(&self) -> usize {
+        let length = self.lua.context(|lua_ctx| -> rlua::Result<Option<usize>> {
+            match lua_ctx.globals().get::<_, Option<Table>>(CONFIG_TABLE)? {
+                Some(config) => config.get("history_length"),
+                None => Ok(None),
+            }
+        });
+        match (length, self.project.manifest.runtime.history_length) {
+            (Ok(Some(length)), _) if length > 0 => length,
+            (_, Some(length)) if length > 0 => length,
+            _ => DEFAULT_HISTORY_LENGTH,
+        }
+    }
+
+    /// one fixed rate tick of the simulation, scaled and paused by the clock
+    pub fn tick(&self, tick_delta: f64) -> rlua::Result<()> {
+        let advanced = self.clock.borrow_mut().advance(tick_delta);
+        match advanced {
+            Some(delta) => {
+                self.record(RecordEvent::Tick(delta));
+                let time = self.clock.borrow().time();
+                self.update(delta, time)?;
+                self.record_history(String::from("tick"))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// update `mp.is_*_down` and call the matching hook
+    pub fn handle_input(&self, event: InputEvent) -> rlua::Result<()> {
+        self.record(RecordEvent::Input(event.clone()));
+        match event {
+            InputEvent::MouseMove {
+                x,
+                y,
+                dx,
+                dy,
+                captured,
+            } => {
+                self.set_input_state("mouse", "x", x)?;
+                self.set_input_state("mouse", "y", y)?;
+                if !captured {
+                    self.call_hook("on_mouse_move", (x, y, dx, dy))?;
+                }
+            }
+            InputEvent::MouseDown { button, x, y } => {
+                self.set_input_state("buttons", &button, true)?;
+                self.call_hook("on_mouse_down", (button, x, y))?;
+            }
+            InputEvent::MouseUp {
+                button,
+                x,
+                y,
+                captured,
+            } => {
+                self.set_input_state("buttons", &button, Value::Nil)?;
+                if !captured {
+                    self.call_hook("on_mouse_up", (button, x, y))?;
+                }
+            }
+            InputEvent::KeyDown { key, mods, repeat } => {
+                self.set_input_state("keys", &key, true)?;
+                self.call_hook("on_key_down", (key, LuaKeyMods(mods), repeat))?;
+            }
+            InputEvent::KeyUp {
+                key,
+                mods,
+                captured,
+            } => {
+                self.set_input_state("keys", &key, Value::Nil)?;
+                if !captured {
+                    self.call_hook("on_key_up", (key, LuaKeyMods(mods)))?;
+                }
+            }
+            InputEvent::Text(text) => self.call_hook("on_text", text)?,
+            InputEvent::Wheel { x, y } => self.call_hook("on_wheel", (x, y))?,
+        }
+        Ok(())
+    }
+
+    /// append every following tick, selection and input event to a `.mpr` file
+    pub fn start_recording(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        *self.recorder.borrow_mut() = Some(Recorder::create(path)?);
+        // an absolute path still opens the project when the replay runs from another directory
+        let script = match fs::canonicalize(&self.project.input) {
+            Ok(path) => path.display().to_string(),
+            Err(_) => self.project.input.clone(),
+        };
+        self.record(RecordEvent::Start {
+            script,
+            source: Some(self.source.clone()),
+            seed: self.seed,
+        });
+        Ok(())
+    }
+
+    /// open the project of a recording's start event and load the entry source it recorded,
+    /// with a warning when the file on disk has changed since
+    pub fn from_recording(start: &RecordEvent) -> Result<Self, Box<dyn Error>> {
+        let (script, source, seed) = match start {
+            RecordEvent::Start {
+                script,
+                source,
+                seed,
+            } => (script, source, *seed),
+            _ => return Err(From::from("a recording has to begin with its start event")),
+        };
+        let project = Project::open(script)?;
+        let on_disk = fs::read_to_string(&project.entry_file)?;
+        let changed = source.as_ref().map_or(false, |source| *source != on_disk);
+        let entry_file = project.entry_file.clone();
+        let mp_lua = MpLua::with_source(project, seed, source.clone().unwrap_or(on_disk))?;
+        if changed {
+            let text = format!(
+                "{} changed since it was recorded, replaying the recorded source",
+                entry_file.display()
+            );
+            println!("[ReplayWarning]{}", text);
+            mp_lua.console_log(text);
+        }
+        Ok(mp_lua)
+    }
+
+    /// while set, ui changes to the simulation are ignored, the recording makes them
+    pub fn set_replaying(&mut self, replaying: bool) {
+        self.replaying = replaying;
+    }
+
+    fn record(&self, event: RecordEvent) {
+        let result = match &mut *self.recorder.borrow_mut() {
+            Some(recorder) => self.lua.context(|lua_ctx| recorder.write(lua_ctx, &event)),
+            None => Ok(()),
+        };
+        self.log_result(&result);
+    }
+
+    /// feed recorded events up to and including the next tick, nothing while paused.
+    /// returns the host commands they queued
+    pub fn replay_frame(
+        &mut self,
+        events: &mut VecDeque<RecordEvent>,
+    ) -> rlua::Result<Vec<HostCommand>> {
+        let mut host_commands = vec![];
+        if !self.clock.borrow_mut().take_tick() {
+            return Ok(host_commands);
+        }
+        while let Some(event) = events.pop_front() {
+            host_commands.extend(self.replay_event(&event)?);
+            if let RecordEvent::Tick(_) = event {
+                break;
+            }
+        }
+        Ok(host_commands)
+    }
+
+    pub fn replay_event(&mut self, event: &RecordEvent) -> rlua::Result<Vec<HostCommand>> {
+        match event {
+            // the source and seed were passed to `MpLua::from_recording`
+            RecordEvent::Start { .. } => {}
+            RecordEvent::Tick(delta) => {
+                self.clock.borrow_mut().advance_recorded(*delta);
+                let time = self.clock.borrow().time();
+                self.update(*delta, time)?;
+                self.record_history(String::from("tick"))?;
+            }
+            RecordEvent::Select { path, .. } => self.run_selection(path)?,
+            RecordEvent::SetValue { path, value } => self.apply_selection_value(path, value)?,
+            RecordEvent::Input(input) => self.handle_input(input.clone())?,
+            RecordEvent::Commands => return self.tick_commands(),
+            RecordEvent::Led { path, event } => self.run_led_event(path.as_ref(), event)?,
+            RecordEvent::SetState { path, value } => self
+                .lua
+                .context(|lua_ctx| set_state_path(lua_ctx, path, value))?,
+            RecordEvent::Eval(code) => self.print_eval(code),
+            // the pause that followed is in the recording as the ticks that are missing
+            RecordEvent::Restore(state) => {
+                self.lua.context(|lua_ctx| restore_state(lua_ctx, state))?
+            }
+            RecordEvent::Reload(source) => {
+                self.console_log(format!("reloading {}", self.project.entry_file.display()));
+                let result = self.reload(source);
+                self.finish_reload(result);
+            }
+        }
+        Ok(vec![])
+    }
+
+    /// call a global function defined by the script, if there is one
+    pub fn call_hook<A: for<'lua> ToLuaMulti<'lua>>(
+        &self,
+        name: &str,
+        args: A,
+    ) -> rlua::Result<()> {
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            if let Some(hook) = globals.get::<_, Option<Function>>(name)? {
+                hook.call::<_, ()>(args)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// polled by `mp.is_key_down`, `mp.is_mouse_down` and `mp.mouse_pos`,
+    /// e.g. `("keys", "space", true)`
+    pub fn set_input_state<V: for<'lua> ToLua<'lua>>(
+        &self,
+        group: &str,
+        key: &str,
+        value: V,
+    ) -> rlua::Result<()> {
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            globals
+                .get::<_, Table>(INPUT_TABLE)?
+                .get::<_, Table>(group)?
+                .set(key, value)
+        })
+    }
+
+    pub fn dump_state(&self, format: DumpFormat) -> rlua::Result<String> {
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            dump_value(globals.get::<_, Value>("mp_state")?, format, 0)
+        })
+    }
+
+    fn build_ui_status(&self, filter: &str) -> rlua::Result<UiStatus> {
+        let mut info = UiStatus::new();
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            let state = globals.get::<_, Table>(STATE_TABLE)?;
+            info.build(lua_ctx, state, filter)?;
             Ok(())
         })?;
 
@@ -247,7 +789,7 @@ impl MpLua {
     }
 
     fn build_ui_selection(&self) -> rlua::Result<UiSelection> {
-        let mut selection = UiSelection { items: vec![] };
+        let mut selection = UiSelection::new(None);
         self.lua.context(|lua_ctx| {
             let globals = lua_ctx.globals();
             let mp_selection = globals.get::<_, Table>("mp_selection")?;
@@ -257,50 +799,258 @@ impl MpLua {
         Ok(selection)
     }
 
-    fn build_ui_led(&self) -> rlua::Result<Led> {
+    /// the windows that could be built, and why the others couldn't
+    fn build_ui_windows(&self) -> rlua::Result<(UiWindows, Vec<rlua::Error>)> {
+        let mut windows = UiWindows::new();
+        let errors = self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            match globals.get::<_, Option<Table>>("mp_windows")? {
+                Some(mp_windows) => Ok(windows.build(mp_windows)),
+                None => Ok(vec![]),
+            }
+        })?;
+        Ok((windows, errors))
+    }
+
+    /// `mp_led` for the led window, or the `led` field of a led item
+    fn led_table<'lua>(
+        lua_ctx: rlua::Context<'lua>,
+        path: Option<&ItemPath>,
+    ) -> rlua::Result<Option<Table<'lua>>> {
+        let globals = lua_ctx.globals();
+        match path {
+            None => globals.get("mp_led"),
+            Some(path) => item_table_field(lua_ctx, path, "led"),
+        }
+    }
+
+    fn build_ui_led(&self, path: Option<&ItemPath>) -> rlua::Result<Led> {
         let mut led: Led = Default::default();
         self.lua.context(|lua_ctx| {
-            let globals = lua_ctx.globals();
-            let is_have_led = globals.contains_key("mp_led")?;
-            if is_have_led {
-                let mp_led = globals.get::<_, Table>("mp_led")?;
-                led.build(mp_led)?;
+            if let Some(led_table) = MpLua::led_table(lua_ctx, path)? {
+                led.build(led_table)?;
             }
             Ok(())
         })?;
         Ok(led)
     }
 
-    pub fn make_status_render<'ui>(
+    pub fn build_show(&self) -> rlua::Result<Show> {
+        let mut show: Show = Default::default();
+        self.lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            if let Some(mp_show) = globals.get::<_, Option<Table>>("mp_show")? {
+                show.build(mp_show)?;
+            }
+            Ok(())
+        })?;
+        Ok(show)
+    }
+
+    /// an edit in the status window
+    pub fn set_state_value<T: for<'lua> ToLua<'lua>>(
         &self,
+        path: &[StateKey],
+        value: T,
+    ) -> rlua::Result<()> {
+        if self.replaying {
+            return Ok(());
+        }
+        self.lua.context(|lua_ctx| {
+            let value = value.to_lua(lua_ctx)?;
+            if let Some(recorded) = StateValue::capture(lua_ctx, value.clone(), &mut vec![])? {
+                self.record(RecordEvent::SetState {
+                    path: path.to_vec(),
+                    value: recorded,
+                });
+            }
+            set_state_path(lua_ctx, path, value)
+        })
+    }
+
+    pub fn make_status_render<'ui>(
+        &'ui self,
         ui: &'ui imgui::Ui,
-        ctx: &Context,
+        filter: &'ui mut ImString,
     ) -> Box<dyn FnOnce() + 'ui> {
-        match self.build_ui_status(ctx) {
-            Ok(status) => Box::new(move || {
-                for item in status.items {
-                    match item {
-                        UiStatusItem::Text(text) => {
-                            ui.text(text);
-                        }
-                    }
+        let reload_error = self.reload_error.clone();
+        let status = self.build_ui_status(filter.to_str());
+        if let Err(e) = &status {
+            self.report_error(e);
+        }
+        Box::new(move || {
+            // shown even when `mp_state` can't be, a failed reload is the usual cause
+            if let Some(e) = reload_error {
+                ui.text_colored([1.0, 0.3, 0.3, 1.0], &im_str!("{}", e));
+                ui.separator();
+            }
+            match status {
+                Ok(status) => {
+                    ui.input_text(im_str!("filter"), filter).build();
+                    ui.separator();
+                    status.render(ui, self);
                 }
-            }),
-            Err(e) => {
-                println!("make render: {:?}", e);
-                Box::new(move || {})
+                Err(_) => ui.text_colored(
+                    [1.0, 0.3, 0.3, 1.0],
+                    im_str!("mp_state can't be shown, see the console"),
+                ),
             }
-        }
+        })
     }
 
-    pub fn run_selection(&self, index: usize) -> rlua::Result<()> {
+    pub fn run_selection(&self, path: &ItemPath) -> rlua::Result<()> {
         self.lua.context(|lua_ctx| {
-            let globals = lua_ctx.globals();
-            let mp_selection = globals.get::<_, Table>("mp_selection")?;
-            let func = mp_selection
-                .get::<_, Table>(index)?
-                .get::<_, Function>("callback")?;
+            let func = path.table(lua_ctx)?.get::<_, Function>("callback")?;
             func.call::<(), ()>(())
+        })?;
+        self.record_history(self.selection_label(path))
+    }
+
+    /// a button of `mp_selection` or of a window clicked in the ui
+    pub fn click_selection(&self, path: &ItemPath) -> rlua::Result<()> {
+        if self.replaying {
+            return Ok(());
+        }
+        self.record(RecordEvent::Select {
+            path: path.clone(),
+            text: self.selection_text(path).unwrap_or_default(),
+        });
+        self.run_selection(path)
+    }
+
+    fn selection_text(&self, path: &ItemPath) -> Option<String> {
+        self.item_props(path).ok().and_then(|props| props.text)
+    }
+
+    /// `text`, `enabled`, `visible` and `tooltip` of an item for this frame
+    pub fn item_props(&self, path: &ItemPath) -> rlua::Result<ItemProps> {
+        self.lua.context(|lua_ctx| {
+            let item = path.table(lua_ctx)?;
+            let mut props = read_props(&item)?;
+            if props.id.is_none() {
+                props.id = Some(item_identity(lua_ctx, &item)?.to_string());
+            }
+            Ok(props)
+        })
+    }
+
+    fn selection_label(&self, path: &ItemPath) -> String {
+        match self.selection_text(path) {
+            Some(text) => format!("{}: {}", path, text),
+            None => path.to_string(),
+        }
+    }
+
+    /// keep a copy of `mp_state` for the timeline
+    fn record_history(&self, label: String) -> rlua::Result<()> {
+        let (state, _) = self.lua.context(capture_state)?;
+        let time = self.clock.borrow().time();
+        let mut history = self.history.borrow_mut();
+        history.set_capacity(self.history_length());
+        history.record(label, time, state);
+        Ok(())
+    }
+
+    /// restoring pauses the clock, so the restored frame stays on screen
+    fn restore_history(&self, index: usize) -> rlua::Result<()> {
+        if self.replaying {
+            return Ok(());
+        }
+        let state = match self.history.borrow().get(index) {
+            Some(frame) => frame.state.clone(),
+            None => return Ok(()),
+        };
+        self.record(RecordEvent::Restore(state.clone()));
+        self.lua.context(|lua_ctx| restore_state(lua_ctx, &state))?;
+        self.clock.borrow_mut().paused = true;
+        Ok(())
+    }
+
+    /// the restore button of the snapshot window
+    fn restore_snapshot(&self, name: &str) -> rlua::Result<()> {
+        if self.replaying {
+            return Ok(());
+        }
+        let (state, _) = self.lua.context(|lua_ctx| {
+            self.snapshots.lock().load(lua_ctx, name)?;
+            capture_state(lua_ctx)
+        })?;
+        self.record(RecordEvent::Restore(state));
+        Ok(())
+    }
+
+    pub fn make_timeline_render<'ui>(
+        &'ui self,
+        ui: &'ui imgui::Ui,
+        timeline: &'ui mut Timeline,
+    ) -> Box<dyn FnOnce() + 'ui> {
+        Box::new(move || {
+            let restore = timeline.render(ui, &self.history.borrow());
+            if let Some(index) = restore {
+                self.log_result(&self.restore_history(index));
+            }
+        })
+    }
+
+    /// the current value of a selection widget: the `bind` path in `mp_state`,
+    /// or the `value` field of the selection itself
+    pub fn selection_value<T: for<'lua> FromLua<'lua>>(
+        &self,
+        path: &ItemPath,
+    ) -> rlua::Result<Option<T>> {
+        self.lua.context(|lua_ctx| {
+            let item = path.table(lua_ctx)?;
+            match item.get::<_, Option<String>>("bind")? {
+                Some(path) => get_state_path(lua_ctx, &parse_state_path(&path)),
+                None => item.get("value"),
+            }
+        })
+    }
+
+    /// a selection widget changed in the ui
+    pub fn set_selection_value<T: for<'lua> ToLua<'lua>>(
+        &self,
+        path: &ItemPath,
+        value: T,
+    ) -> rlua::Result<()> {
+        if self.replaying {
+            return Ok(());
+        }
+        self.apply_selection_value(path, value)
+    }
+
+    /// store the new value of a selection widget and call its callback with it
+    fn apply_selection_value<T: for<'lua> ToLua<'lua>>(
+        &self,
+        path: &ItemPath,
+        value: T,
+    ) -> rlua::Result<()> {
+        self.lua.context(|lua_ctx| {
+            let item = path.table(lua_ctx)?;
+            let value = value.to_lua(lua_ctx)?;
+            if let Some(recorded) = StateValue::capture(lua_ctx, value.clone(), &mut vec![])? {
+                self.record(RecordEvent::SetValue {
+                    path: path.clone(),
+                    value: recorded,
+                });
+            }
+            match item.get::<_, Option<String>>("bind")? {
+                Some(path) => set_state_path(lua_ctx, &parse_state_path(&path), value.clone())?,
+                None => item.set("value", value.clone())?,
+            }
+            if let Some(callback) = item.get::<_, Option<Function>>("callback")? {
+                callback.call::<_, ()>(value)?;
+            }
+            Ok(())
+        })?;
+        self.record_history(self.selection_label(path))
+    }
+
+    /// the value at `path` in `mp_state` as the status window shows it
+    pub fn display_state_path(&self, path: &[StateKey]) -> rlua::Result<String> {
+        self.lua.context(|lua_ctx| {
+            let value = get_state_path::<Value>(lua_ctx, path)?;
+            Ok(display_value(&value))
         })
     }
 
@@ -309,51 +1059,266 @@ impl MpLua {
         ui: &'ui imgui::Ui,
     ) -> Box<dyn FnOnce() + 'ui> {
         match &self.selections {
-            Some(rc_selections) => Box::new(move || {
-                for item in &rc_selections.items {
-                    match item {
-                        UiSelectionItem::Button { index, text } => {
-                            if ui.button(&im_str!("{}", &text), [200f32, 30f32]) {
-                                log_lua_result(&self.run_selection(*index));
-                            }
-                        }
-                    }
-                }
-            }),
+            Some(rc_selections) => Box::new(move || rc_selections.render(ui, self)),
             None => Box::new(move || {}),
         }
     }
 
+    /// a click or hover in the led window or a led item
+    fn handle_led_event(&self, path: Option<&ItemPath>, event: LedEvent) -> rlua::Result<()> {
+        if self.replaying {
+            return Ok(());
+        }
+        self.record(RecordEvent::Led {
+            path: path.cloned(),
+            event: event.clone(),
+        });
+        self.run_led_event(path, &event)
+    }
+
+    fn run_led_event(&self, path: Option<&ItemPath>, event: &LedEvent) -> rlua::Result<()> {
+        self.lua.context(|lua_ctx| {
+            let mp_led = match MpLua::led_table(lua_ctx, path)? {
+                Some(mp_led) => mp_led,
+                None => return Ok(()),
+            };
+            match event {
+                LedEvent::Click { x, y, button } => {
+                    if let Some(on_click) = mp_led.get::<_, Option<Function>>("on_click")? {
+                        on_click.call::<_, ()>((*x, *y, *button))?;
+                    }
+                }
+                LedEvent::Hover { x, y } => {
+                    if let Some(on_hover) = mp_led.get::<_, Option<Function>>("on_hover")? {
+                        on_hover.call::<_, ()>((*x, *y))?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
     pub fn make_led_render<'ui>(
         &'ui self,
         ui: &'ui imgui::Ui,
-        cell_size: f32,
+        input: &'ui mut LedInput,
     ) -> Box<dyn FnOnce() + 'ui> {
-        match self.build_ui_led() {
+        match self.build_ui_led(None) {
             Ok(led) => Box::new(move || {
-                let c0 = [0.5, 1.0, 1.0, 1.0];
-                let c1 = [0.1, 0.1, 0.1, 1.0];
-                let draw_list = ui.get_window_draw_list();
-                let win_pos = ui.window_pos();
-                for (i, b) in led.buf.iter().enumerate() {
-                    let row = (i / LED_SIZE) as f32;
-                    let col = (i % LED_SIZE) as f32;
-                    let x1 = row * cell_size + win_pos[0] + 32.0;
-                    let y1 = col * cell_size + win_pos[1] + 32.0;
-                    draw_list.add_rect_filled_multicolor(
-                        [x1, y1],
-                        [x1 + cell_size - 4.0, y1 + cell_size - 4.0],
-                        if *b { c0 } else { c1 },
-                        if *b { c0 } else { c1 },
-                        if *b { c0 } else { c1 },
-                        if *b { c0 } else { c1 },
-                    );
+                for event in led.render(ui, input) {
+                    self.log_result(&self.handle_led_event(None, event));
                 }
             }),
             Err(e) => {
-                println!("make led render: {:?}", e);
+                self.report_error(&e);
                 Box::new(move || {})
             }
         }
     }
+
+    pub fn render_led_item(
+        &self,
+        ui: &imgui::Ui,
+        path: &ItemPath,
+        input: &mut LedInput,
+        enabled: bool,
+    ) -> rlua::Result<()> {
+        let led = self.build_ui_led(Some(path))?;
+        let events = led.render(ui, input);
+        if !enabled {
+            return Ok(());
+        }
+        for event in events {
+            self.handle_led_event(Some(path), event)?;
+        }
+        Ok(())
+    }
+
+    pub fn render_canvas_item(
+        &self,
+        ui: &imgui::Ui,
+        path: &ItemPath,
+        size: [f32; 2],
+    ) -> rlua::Result<()> {
+        let mut show: Show = Default::default();
+        self.lua.context(|lua_ctx| {
+            if let Some(shapes) = item_table_field(lua_ctx, path, "shapes")? {
+                show.build(shapes)?;
+            }
+            Ok(())
+        })?;
+        show.draw_ui(ui, size);
+        Ok(())
+    }
+
+    pub fn make_plots_render<'ui>(&'ui self, ui: &'ui imgui::Ui) -> Box<dyn FnOnce() + 'ui> {
+        Box::new(move || {
+            if !self.plots.lock().render(ui) {
+                return;
+            }
+            let written = self.plots.lock().write_csv().map(Path::to_path_buf);
+            match written {
+                Ok(path) => self.console_log(format!(
+                    "plots written to {}, replacing the previous export",
+                    path.display()
+                )),
+                Err(e) => self.report_error(&rlua::Error::external(e)),
+            }
+        })
+    }
+
+    /// the windows of `mp_windows`, each one builds its own imgui window
+    pub fn render_windows(&self, ui: &imgui::Ui) {
+        if let Some(windows) = &self.windows {
+            windows.render(ui, self);
+        }
+    }
+
+    pub fn make_console_render<'ui>(
+        &'ui self,
+        ui: &'ui imgui::Ui,
+        show_status: &'ui mut bool,
+        repl: &'ui mut Repl,
+    ) -> Box<dyn FnOnce() + 'ui> {
+        Box::new(move || {
+            let footer_height = ui.frame_height_with_spacing();
+            if self.console.borrow_mut().render(ui, footer_height) {
+                *show_status = true;
+            }
+            repl.render(ui, self);
+        })
+    }
+
+    /// clock hotkeys are handled here so they keep working while paused
+    pub fn handle_clock_key(&self, key: KeyCode) -> bool {
+        self.clock.borrow_mut().handle_key(key)
+    }
+
+    pub fn make_control_render<'ui>(&'ui self, ui: &'ui imgui::Ui) -> Box<dyn FnOnce() + 'ui> {
+        let tick_rate = self.tick_rate();
+        let seed = self.rng.lock().seed();
+        Box::new(move || {
+            self.clock.borrow_mut().render(ui, tick_rate);
+            ui.text(&im_str!("seed {}", seed));
+        })
+    }
+
+    pub fn make_snapshot_render<'ui>(
+        &'ui self,
+        ui: &'ui imgui::Ui,
+        name: &'ui mut ImString,
+    ) -> Box<dyn FnOnce() + 'ui> {
+        Box::new(move || {
+            ui.input_text(im_str!("name"), name).build();
+            ui.same_line(0.0);
+            if ui.button(im_str!("save"), [0.0, 0.0]) {
+                let result = self.lua.context(|lua_ctx| {
+                    let skipped = self.snapshots.lock().save(lua_ctx, name.to_str())?;
+                    if let Some(message) = skipped_message(name.to_str(), &skipped) {
+                        self.console.borrow_mut().push(ConsoleLevel::Error, message);
+                    }
+                    Ok(())
+                });
+                self.log_result(&result);
+            }
+            ui.separator();
+
+            let names = self.snapshots.lock().names();
+            for slot in names {
+                ui.text(&im_str!("{}", slot));
+                ui.same_line(0.0);
+                if ui.small_button(&im_str!("restore##{}", slot)) {
+                    self.log_result(&self.restore_snapshot(&slot));
+                }
+                ui.same_line(0.0);
+                if ui.small_button(&im_str!("delete##{}", slot)) {
+                    self.log_result(&self.snapshots.lock().delete(&slot));
+                }
+            }
+        })
+    }
+
+    /// evaluate a line typed in the repl, `1 + 1` is tried as `return 1 + 1` first
+    fn eval(&self, code: &str) -> rlua::Result<Vec<String>> {
+        self.lua.context(|lua_ctx| {
+            let expression = format!("return {}", code);
+            let function = match lua_ctx
+                .load(&expression)
+                .set_name(REPL_CHUNK)?
+                .into_function()
+            {
+                Ok(function) => function,
+                Err(_) => lua_ctx.load(code).set_name(REPL_CHUNK)?.into_function()?,
+            };
+            let values = function.call::<_, MultiValue>(())?;
+            Ok(values
+                .iter()
+                .map(|value| pretty_value(value, REPL_DEPTH))
+                .collect())
+        })
+    }
+
+    pub fn run_repl(&self, code: &str) {
+        if self.replaying {
+            self.console.borrow_mut().push(
+                ConsoleLevel::Error,
+                String::from("the repl is disabled while a recording plays"),
+            );
+            return;
+        }
+        self.record(RecordEvent::Eval(String::from(code)));
+        self.print_eval(code);
+    }
+
+    fn print_eval(&self, code: &str) {
+        match self.eval(code) {
+            Ok(results) => {
+                let mut message = format!("> {}", code);
+                for result in results {
+                    message.push('\n');
+                    message.push_str(&result);
+                }
+                self.console.borrow_mut().push(ConsoleLevel::Eval, message);
+            }
+            Err(e) => {
+                self.console
+                    .borrow_mut()
+                    .push(ConsoleLevel::Eval, format!("> {}", code));
+                self.report_error(&e);
+            }
+        }
+    }
+
+    /// names that complete `word`, a global name or a dotted path like `mp_state.go`
+    pub fn complete(&self, word: &str) -> rlua::Result<Vec<String>> {
+        let (path, last) = match word.rfind('.') {
+            Some(i) => (&word[..=i], &word[i + 1..]),
+            None => ("", word),
+        };
+        self.lua.context(|lua_ctx| {
+            let mut table = lua_ctx.globals();
+            for name in path.split('.').filter(|name| !name.is_empty()) {
+                // raw, so the erroring `__index` of `mp` doesn't fire
+                match table.raw_get::<_, Value>(name)? {
+                    Value::Table(inner) => table = inner,
+                    _ => return Ok(vec![]),
+                }
+            }
+            let mut names = vec![];
+            for pair in table.pairs::<Value, Value>() {
+                if let (Value::String(key), _) = pair? {
+                    let key = key.to_str()?;
+                    if key.starts_with(last) {
+                        names.push(format!("{}{}", path, key));
+                    }
+                }
+            }
+            names.sort();
+            Ok(names)
+        })
+    }
+
+    pub fn console_log(&self, text: String) {
+        self.console.borrow_mut().push(ConsoleLevel::Log, text);
+    }
 }
@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::console::error_text;
+use crate::lua::MpLua;
+use crate::project::Project;
+
+const TEST_FILE_SUFFIX: &str = "_test.lua";
+const TEST_SEED: u64 = 0;
+
+/// the entry file itself plus `<stem>_test.lua` next to it, if there is one
+fn test_files(entry_file: &Path) -> Vec<Option<PathBuf>> {
+    let mut files = vec![None];
+    if let Some(stem) = entry_file.file_stem().and_then(|stem| stem.to_str()) {
+        let test_file = entry_file.with_file_name(format!("{}{}", stem, TEST_FILE_SUFFIX));
+        if test_file.is_file() {
+            files.push(Some(test_file));
+        }
+    }
+    files
+}
+
+/// lua errors print like they do in the console, with the traceback after the message
+fn failure_text(error: &(dyn Error + 'static)) -> String {
+    match error.downcast_ref::<rlua::Error>() {
+        Some(error) => error_text(error),
+        None => error.to_string(),
+    }
+}
+
+/// a fresh state for every test: load the entry file, run `awake`, then load the test file.
+/// the seed is fixed, so tests using random numbers don't flake.
+/// also returns the tests the test file adds, so the entry file's own tests only run
+/// once, for `None`
+fn prepare(
+    project: &Project,
+    test_file: &Option<PathBuf>,
+) -> Result<(MpLua, Vec<String>), Box<dyn Error>> {
+    let mut mp_lua = MpLua::with_seed(project.clone(), TEST_SEED)?;
+    mp_lua.awake()?;
+    // a broken `mp_selection` or window is only reported by `awake`
+    mp_lua.check_errors()?;
+    let entry_names = mp_lua.test_names()?;
+    let names = match test_file {
+        Some(test_file) => {
+            mp_lua.load_file(test_file)?;
+            mp_lua
+                .test_names()?
+                .into_iter()
+                .filter(|name| !entry_names.contains(name))
+                .collect()
+        }
+        None => entry_names,
+    };
+    Ok((mp_lua, names))
+}
+
+pub fn test(input_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut passed = 0;
+    let mut failed = 0;
+    let project = Project::open(input_path)?;
+    for test_file in test_files(&project.entry_file) {
+        let file_name = match &test_file {
+            Some(path) => path.display().to_string(),
+            None => project.entry_file.display().to_string(),
+        };
+        // a file that fails to load fails on its own, the other files still run
+        let names = match prepare(&project, &test_file) {
+            Ok((_, names)) => names,
+            Err(e) => {
+                failed += 1;
+                println!("[FAIL] {}\n{}", file_name, failure_text(&*e));
+                continue;
+            }
+        };
+        for name in names {
+            let result = prepare(&project, &test_file).and_then(|(mp_lua, _)| {
+                mp_lua.run_test(&name)?;
+                Ok(())
+            });
+            match result {
+                Ok(_) => {
+                    passed += 1;
+                    println!("[PASS] {} {}", file_name, name);
+                }
+                Err(e) => {
+                    failed += 1;
+                    println!("[FAIL] {} {}\n{}", file_name, name, failure_text(&*e));
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", passed, failed);
+    if failed > 0 {
+        return Err(format!("{} tests failed", failed).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn only_the_entry_stem_test_file_runs() {
+        let dir = std::env::temp_dir().join(format!("maple_test_files_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in &["game.lua", "game_test.lua", "other_test.lua"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let files = test_files(&dir.join("game.lua"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files, vec![None, Some(dir.join("game_test.lua"))]);
+    }
+
+    #[test]
+    fn failures_use_the_console_text() {
+        let cause = rlua::Error::RuntimeError(String::from("boom"));
+        let error: Box<dyn Error> = Box::new(rlua::Error::CallbackError {
+            traceback: String::from("stack traceback:"),
+            cause: std::sync::Arc::new(cause),
+        });
+        assert_eq!(
+            failure_text(&*error),
+            "runtime error: boom\nstack traceback:"
+        );
+        let error: Box<dyn Error> = From::from("not lua");
+        assert_eq!(failure_text(&*error), "not lua");
+    }
+}
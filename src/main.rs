@@ -1,17 +1,49 @@
 use clap::{App, Arg, SubCommand};
 
+mod clock;
+mod console;
+mod dump;
+mod headless;
+mod history;
+mod host;
 mod imgui_wrapper;
+mod input;
+mod led;
 mod lua;
+mod lua_test;
 mod new;
+mod plot;
+mod project;
+mod record;
+mod repl;
+mod rng;
 mod run;
-mod signal;
+mod selection;
+mod shared;
+mod show;
+mod snapshot;
+mod state;
+mod status;
+mod watcher;
+mod windows;
 
+use crate::dump::DumpFormat;
+use crate::headless::{replay_headless, run_headless, HeadlessOptions};
+use crate::lua_test::test;
 use crate::new::new;
-use crate::run::run;
+use crate::run::{replay, run, RunOptions};
 
 const VERSION: &str = "0.1.2";
 
-fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+/// errors are printed with `Display`, a lua error then reads like the console shows it
+fn main() {
+    if let Err(e) = try_main() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn try_main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     let matches = App::new("maple")
         .version(VERSION)
         .author("nalleyer")
@@ -21,25 +53,134 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
                 .about("run you lua folder or file")
                 .version(VERSION)
                 .author("nalleyer")
+                .arg(
+                    Arg::with_name("INPUT")
+                        .required(true)
+                        .help("a lua file, or a project directory with a main.lua or maple.toml"),
+                )
+                .arg(
+                    Arg::with_name("headless")
+                        .long("headless")
+                        .help("run without a window, then print mp_state"),
+                )
+                .arg(
+                    Arg::with_name("frames")
+                        .long("frames")
+                        .takes_value(true)
+                        .default_value("60")
+                        .help("number of update ticks in headless mode"),
+                )
+                .arg(
+                    Arg::with_name("delta")
+                        .long("delta")
+                        .takes_value(true)
+                        .default_value("0.016666667")
+                        .help("delta in seconds passed to update in headless mode"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "lua"])
+                        .default_value("lua")
+                        .help("output format of mp_state in headless mode"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .takes_value(true)
+                        .help("seed of mp.random_* and math.random, random when not set"),
+                )
+                .arg(
+                    Arg::with_name("record")
+                        .long("record")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .conflicts_with("headless")
+                        .help("record clicks, input and frame deltas to a .mpr file"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("play back a .mpr recording made with run --record")
+                .version(VERSION)
+                .author("nalleyer")
+                .arg(Arg::with_name("RECORDING").required(true))
+                .arg(
+                    Arg::with_name("headless")
+                        .long("headless")
+                        .help("replay without a window, then print mp_state"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "lua"])
+                        .default_value("lua")
+                        .help("output format of mp_state in headless mode"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("test")
+                .about("run test_* functions and mp_tests of your lua file")
+                .version(VERSION)
+                .author("nalleyer")
                 .arg(Arg::with_name("INPUT").required(true)),
         )
         .subcommand(
             SubCommand::with_name("new")
-                .about("generate lua scaffold")
+                .about("generate lua scaffold and maple.toml")
                 .version(VERSION)
                 .author("nalleyer")
-                .arg(Arg::with_name("FILENAME").required(true)),
+                .arg(
+                    Arg::with_name("FILENAME")
+                        .required(true)
+                        .help("a lua file, or a directory for a new project"),
+                ),
         )
         .get_matches();
 
     if let Some(matches) = matches.subcommand_matches("run") {
         let input_path = matches.value_of("INPUT").unwrap();
-        run(&input_path)?;
+        let seed = match matches.value_of("seed") {
+            Some(seed) => Some(seed.parse::<u64>()?),
+            None => None,
+        };
+        if matches.is_present("headless") {
+            let options = HeadlessOptions {
+                frames: matches.value_of("frames").unwrap().parse()?,
+                delta: matches.value_of("delta").unwrap().parse()?,
+                format: DumpFormat::from_name(matches.value_of("format").unwrap()).unwrap(),
+                seed,
+            };
+            run_headless(input_path, &options)?;
+        } else {
+            let options = RunOptions {
+                record: matches.value_of("record").map(String::from),
+                seed,
+            };
+            run(input_path, &options)?;
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("replay") {
+        let record_path = matches.value_of("RECORDING").unwrap();
+        if matches.is_present("headless") {
+            let format = DumpFormat::from_name(matches.value_of("format").unwrap()).unwrap();
+            replay_headless(record_path, format)?;
+        } else {
+            replay(record_path)?;
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("test") {
+        let input_path = matches.value_of("INPUT").unwrap();
+        test(input_path)?;
     }
 
     if let Some(matches) = matches.subcommand_matches("new") {
         let file_name = matches.value_of("FILENAME").unwrap();
-        new(&file_name)?;
+        new(file_name)?;
     }
 
     Ok(())
@@ -1,13 +1,78 @@
 use std::fs;
 use std::path::Path;
 
+use crate::project::{default_name, MAIN_FILE, MANIFEST_FILE};
+
+/// every field with its default value, `my_project` is replaced by the project name
+const MANIFEST_CONTENT: &str = r#"name = "my_project"
+entry = "main.lua"
+
+[window]
+# title = "my_project"
+width = 800
+height = 600
+resizable = true
+vsync = true
+
+[ui]
+font_size = 18
+panels = ["status", "selection", "led", "control", "snapshot", "timeline", "plots", "console"]
+
+[ui.layout.status]
+position = [50, 50]
+size = [300, 600]
+
+[ui.layout.selection]
+position = [350, 50]
+size = [300, 600]
+
+[ui.layout.led]
+position = [600, 100]
+size = [300, 300]
+
+[ui.layout.control]
+position = [900, 50]
+size = [300, 150]
+
+[ui.layout.snapshot]
+position = [900, 220]
+size = [300, 200]
+
+[ui.layout.timeline]
+position = [900, 430]
+size = [400, 300]
+
+[ui.layout.plots]
+position = [1310, 50]
+size = [400, 500]
+
+[ui.layout.console]
+position = [50, 660]
+size = [500, 200]
+
+[runtime]
+tick_rate = 60
+"#;
+
+/// a lua file with a `maple.toml` next to it, or a project directory with `main.lua`
+/// when the name has no `.lua` extension. existing files are left alone
 pub fn new(file_name: &str) -> std::io::Result<()> {
     let path = Path::new(file_name);
     if path.exists() {
         println!("file exists! exiting");
         return Ok(());
     }
-    let file_content = r#"
+    let (dir, lua_file) = if path.extension().map_or(false, |ext| ext == "lua") {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => Path::new(".").to_path_buf(),
+        };
+        (dir, path.to_path_buf())
+    } else {
+        fs::create_dir_all(path)?;
+        (path.to_path_buf(), path.join(MAIN_FILE))
+    };
+    let lua_content = r#"
 mp_state = {}
 mp_selection =
 {
@@ -29,6 +94,35 @@ function awake()
 end
 "#;
 
-    fs::write(file_name, file_content)?;
+    fs::write(&lua_file, lua_content)?;
+    let manifest_file = dir.join(MANIFEST_FILE);
+    if manifest_file.exists() {
+        println!("{} exists, not overwriting it", manifest_file.display());
+    } else {
+        let entry = lua_file.file_name().unwrap().to_string_lossy();
+        let name = toml::Value::String(default_name(path)).to_string();
+        let manifest_content = MANIFEST_CONTENT
+            .replacen("main.lua", &entry, 1)
+            .replace("\"my_project\"", &name);
+        fs::write(&manifest_file, manifest_content)?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::{Manifest, Panel, UiConfig};
+
+    #[test]
+    fn manifest_content_is_the_default_manifest() {
+        let manifest: Manifest = toml::from_str(MANIFEST_CONTENT).unwrap();
+        let defaults = UiConfig::default();
+        assert_eq!(manifest.name.as_deref(), Some("my_project"));
+        assert_eq!(manifest.entry.as_deref(), Some(MAIN_FILE));
+        assert_eq!(manifest.ui.panels, defaults.panels);
+        for panel in Panel::ALL.iter() {
+            assert_eq!(manifest.ui.layout(*panel), defaults.layout(*panel));
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use imgui::{im_str, ImString, Slider};
+use rlua::{Function, Table};
+
+use crate::state::{get_state_path, parse_state_path};
+
+pub const DEFAULT_LENGTH: usize = 600;
+const MAX_LENGTH: usize = 100_000;
+const PLOT_HEIGHT: f32 = 80.0;
+pub const CSV_FILE: &str = "plots.csv";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlotKind {
+    Lines,
+    Histogram,
+}
+
+/// how a series of `mp_plots` is shown, `mp.plot` series use the defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesConfig {
+    pub kind: PlotKind,
+    /// how many samples are kept
+    pub length: usize,
+    /// scaled to the samples when not set
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl Default for SeriesConfig {
+    fn default() -> Self {
+        SeriesConfig {
+            kind: PlotKind::Lines,
+            length: DEFAULT_LENGTH,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+struct Series {
+    name: String,
+    config: SeriesConfig,
+    /// `(time, value)`, the oldest samples are dropped past `config.length`
+    samples: VecDeque<(f64, f32)>,
+}
+
+impl Series {
+    fn new(name: &str) -> Self {
+        Series {
+            name: String::from(name),
+            config: SeriesConfig::default(),
+            samples: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, time: f64, value: f32) {
+        while self.samples.len() >= self.config.length {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((time, value));
+    }
+
+    /// `(min, max)` of the plot, the configured bounds or the range of the samples
+    fn scale(&self) -> (f32, f32) {
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+        for (_, value) in &self.samples {
+            min = min.min(*value);
+            max = max.max(*value);
+        }
+        let min = self.config.min.unwrap_or(min);
+        let max = self.config.max.unwrap_or(max);
+        if min < max {
+            (min, max)
+        } else {
+            // a flat line would sit on the bottom edge
+            (min - 1.0, min + 1.0)
+        }
+    }
+
+    fn render(&self, ui: &imgui::Ui, shown: usize) {
+        let skip = self.samples.len().saturating_sub(shown);
+        let values = self
+            .samples
+            .iter()
+            .skip(skip)
+            .map(|(_, value)| *value)
+            .collect::<Vec<_>>();
+        let (min, max) = self.scale();
+        let overlay = match values.last() {
+            Some(last) => im_str!("{}  min {:.2}  max {:.2}", last, min, max),
+            None => ImString::new("no samples"),
+        };
+        let label = im_str!("{}", self.name);
+        let size = [0.0, PLOT_HEIGHT];
+        match self.config.kind {
+            PlotKind::Lines => ui
+                .plot_lines(&label, &values)
+                .scale_min(min)
+                .scale_max(max)
+                .graph_size(size)
+                .overlay_text(&overlay)
+                .build(),
+            PlotKind::Histogram => ui
+                .plot_histogram(&label, &values)
+                .scale_min(min)
+                .scale_max(max)
+                .graph_size(size)
+                .overlay_text(&overlay)
+                .build(),
+        }
+    }
+}
+
+/// a series of `mp_plots` and its value this tick, `None` when there is no value to plot
+pub struct DeclaredSample {
+    pub name: String,
+    pub config: SeriesConfig,
+    pub value: Option<f32>,
+}
+
+/// `mp_plots = { length = 600, { name, bind = "path" | value = function, type, length, min, max } }`,
+/// `type` is `lines` or `histogram`, the outer `length` is the default of every series
+pub fn sample_declared(
+    lua_ctx: rlua::Context,
+    mp_plots: Table,
+) -> rlua::Result<Vec<DeclaredSample>> {
+    let length = mp_plots
+        .get::<_, Option<usize>>("length")?
+        .unwrap_or(DEFAULT_LENGTH);
+    let mut samples = vec![];
+    for plot in mp_plots.sequence_values::<Table>() {
+        let plot = plot?;
+        let name = plot.get::<_, String>("name")?;
+        let kind = match plot.get::<_, Option<String>>("type")?.as_deref() {
+            None | Some("lines") => PlotKind::Lines,
+            Some("histogram") => PlotKind::Histogram,
+            Some(other) => {
+                return Err(rlua::Error::RuntimeError(format!(
+                    "mp_plots {}: unknown type {}",
+                    name, other
+                )))
+            }
+        };
+        let config = SeriesConfig {
+            kind,
+            length: plot.get::<_, Option<usize>>("length")?.unwrap_or(length),
+            min: plot.get("min")?,
+            max: plot.get("max")?,
+        };
+        let value = match plot.get::<_, Option<String>>("bind")? {
+            Some(bind) => get_state_path(lua_ctx, &parse_state_path(&bind))?,
+            None => match plot.get::<_, Option<Function>>("value")? {
+                Some(value) => value.call(())?,
+                None => None,
+            },
+        };
+        samples.push(DeclaredSample {
+            name,
+            config,
+            value,
+        });
+    }
+    Ok(samples)
+}
+
+/// the series of `mp_plots` and `mp.plot`, in the order they first got a sample
+pub struct Plots {
+    series: Vec<Series>,
+    /// the time of the tick being run, set before `update` so `mp.plot` can use it
+    pub time: f64,
+    /// how many of the newest samples the window shows
+    shown: i32,
+    csv_file: PathBuf,
+}
+
+impl Plots {
+    pub fn new(project_dir: &Path) -> Self {
+        Plots {
+            series: vec![],
+            time: 0.0,
+            shown: DEFAULT_LENGTH as i32,
+            csv_file: project_dir.join(CSV_FILE),
+        }
+    }
+
+    fn series_mut(&mut self, name: &str) -> &mut Series {
+        match self.series.iter().position(|series| series.name == name) {
+            Some(index) => &mut self.series[index],
+            None => {
+                self.series.push(Series::new(name));
+                self.series.last_mut().unwrap()
+            }
+        }
+    }
+
+    pub fn configure(&mut self, name: &str, config: SeriesConfig) {
+        let mut config = config;
+        config.length = config.length.max(1).min(MAX_LENGTH);
+        self.series_mut(name).config = config;
+    }
+
+    pub fn push(&mut self, name: &str, value: f32) {
+        let time = self.time;
+        self.series_mut(name).push(time, value);
+    }
+
+    pub fn clear(&mut self) {
+        for series in &mut self.series {
+            series.samples.clear();
+        }
+    }
+
+    /// one `series,time,value` row per sample, the file of the last export is overwritten
+    pub fn write_csv(&self) -> io::Result<&Path> {
+        let mut file = File::create(&self.csv_file)?;
+        writeln!(file, "series,time,value")?;
+        for series in &self.series {
+            let name = series.name.replace('"', "\"\"");
+            for (time, value) in &series.samples {
+                writeln!(file, "\"{}\",{},{}", name, time, value)?;
+            }
+        }
+        Ok(&self.csv_file)
+    }
+
+    /// returns true when the user asked for a csv export
+    pub fn render(&mut self, ui: &imgui::Ui) -> bool {
+        let export = ui.button(im_str!("export csv"), [0.0, 0.0]);
+        ui.same_line(0.0);
+        if ui.button(im_str!("clear"), [0.0, 0.0]) {
+            self.clear();
+        }
+        let longest = self
+            .series
+            .iter()
+            .map(|series| series.config.length)
+            .max()
+            .unwrap_or(DEFAULT_LENGTH)
+            .max(2) as i32;
+        // the series may have been shortened since `shown` was set
+        self.shown = self.shown.max(2).min(longest);
+        Slider::new(im_str!("samples"), 2..=longest).build(ui, &mut self.shown);
+        ui.separator();
+
+        if self.series.is_empty() {
+            ui.text(im_str!("declare mp_plots or call mp.plot(name, value)"));
+        }
+        for series in &self.series {
+            series.render(ui, self.shown as usize);
+        }
+        export
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(length: usize, values: &[f32]) -> Series {
+        let mut series = Series::new("test");
+        series.config.length = length;
+        for (i, value) in values.iter().enumerate() {
+            series.push(i as f64, *value);
+        }
+        series
+    }
+
+    #[test]
+    fn push_keeps_the_newest_samples() {
+        let series = series(3, &[1.0, 2.0, 3.0, 4.0, 5.0]);
+        let values = series.samples.iter().map(|(_, v)| *v).collect::<Vec<_>>();
+        assert_eq!(values, vec![3.0, 4.0, 5.0]);
+        assert_eq!(series.samples.front(), Some(&(2.0, 3.0)));
+    }
+
+    #[test]
+    fn scale_fits_the_samples() {
+        assert_eq!(series(10, &[2.0, -1.0, 4.0]).scale(), (-1.0, 4.0));
+    }
+
+    #[test]
+    fn scale_prefers_configured_bounds() {
+        let mut series = series(10, &[2.0, -1.0, 4.0]);
+        series.config.min = Some(0.0);
+        assert_eq!(series.scale(), (0.0, 4.0));
+        series.config.max = Some(10.0);
+        assert_eq!(series.scale(), (0.0, 10.0));
+    }
+
+    #[test]
+    fn scale_of_a_flat_line_is_not_empty() {
+        assert_eq!(series(10, &[3.0, 3.0]).scale(), (2.0, 4.0));
+    }
+}
@@ -0,0 +1,319 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+pub const MAIN_FILE: &str = "main.lua";
+pub const MANIFEST_FILE: &str = "maple.toml";
+
+/// `maple.toml` in the project root, every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Manifest {
+    /// the ggez game id and default window title, the project directory name when not set
+    pub name: Option<String>,
+    /// the entry file relative to the project root, `main.lua` when not set
+    pub entry: Option<String>,
+    pub window: WindowConfig,
+    pub ui: UiConfig,
+    pub runtime: RuntimeConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    /// the project name when not set
+    pub title: Option<String>,
+    pub width: f32,
+    pub height: f32,
+    pub resizable: bool,
+    pub vsync: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: None,
+            width: 800.0,
+            height: 600.0,
+            resizable: true,
+            vsync: true,
+        }
+    }
+}
+
+/// the built-in windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Panel {
+    Status,
+    Selection,
+    Led,
+    Control,
+    Snapshot,
+    Timeline,
+    Plots,
+    Console,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 8] = [
+        Panel::Status,
+        Panel::Selection,
+        Panel::Led,
+        Panel::Control,
+        Panel::Snapshot,
+        Panel::Timeline,
+        Panel::Plots,
+        Panel::Console,
+    ];
+
+    /// `(position, size)` when the manifest doesn't say
+    fn default_layout(self) -> ([f32; 2], [f32; 2]) {
+        match self {
+            Panel::Status => ([50.0, 50.0], [300.0, 600.0]),
+            Panel::Selection => ([350.0, 50.0], [300.0, 600.0]),
+            Panel::Led => ([600.0, 100.0], [300.0, 300.0]),
+            Panel::Control => ([900.0, 50.0], [300.0, 150.0]),
+            Panel::Snapshot => ([900.0, 220.0], [300.0, 200.0]),
+            Panel::Timeline => ([900.0, 430.0], [400.0, 300.0]),
+            Panel::Plots => ([1310.0, 50.0], [400.0, 500.0]),
+            Panel::Console => ([50.0, 660.0], [500.0, 200.0]),
+        }
+    }
+}
+
+/// where a window first shows up, the user can still move it afterwards
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PanelLayout {
+    pub position: Option<[f32; 2]>,
+    pub size: Option<[f32; 2]>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layout {
+    pub status: PanelLayout,
+    pub selection: PanelLayout,
+    pub led: PanelLayout,
+    pub control: PanelLayout,
+    pub snapshot: PanelLayout,
+    pub timeline: PanelLayout,
+    pub plots: PanelLayout,
+    pub console: PanelLayout,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    pub font_size: f32,
+    /// the windows that are shown, all of them when not set
+    pub panels: Vec<Panel>,
+    pub layout: Layout,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            font_size: 18.0,
+            panels: Panel::ALL.to_vec(),
+            layout: Layout::default(),
+        }
+    }
+}
+
+impl UiConfig {
+    pub fn shows(&self, panel: Panel) -> bool {
+        self.panels.contains(&panel)
+    }
+
+    /// `(position, size)` of a panel, the manifest layout over the default one
+    pub fn layout(&self, panel: Panel) -> ([f32; 2], [f32; 2]) {
+        let layout = match panel {
+            Panel::Status => &self.layout.status,
+            Panel::Selection => &self.layout.selection,
+            Panel::Led => &self.layout.led,
+            Panel::Control => &self.layout.control,
+            Panel::Snapshot => &self.layout.snapshot,
+            Panel::Timeline => &self.layout.timeline,
+            Panel::Plots => &self.layout.plots,
+            Panel::Console => &self.layout.console,
+        };
+        let (position, size) = panel.default_layout();
+        (
+            layout.position.unwrap_or(position),
+            layout.size.unwrap_or(size),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfig {
+    /// ticks per second, `mp_config.tick_rate` in the script wins over it
+    pub tick_rate: Option<u32>,
+    /// frames the timeline keeps, `mp_config.history_length` in the script wins over it
+    pub history_length: Option<usize>,
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> Result<Manifest, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        let manifest: Manifest =
+            toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        manifest
+            .check()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(manifest)
+    }
+
+    /// values serde accepts but ggez and imgui can't use
+    fn check(&self) -> Result<(), String> {
+        let sizes = [
+            ("window.width", self.window.width),
+            ("window.height", self.window.height),
+            ("ui.font_size", self.ui.font_size),
+        ];
+        for (name, value) in sizes.iter() {
+            if value.is_nan() || *value <= 0.0 {
+                return Err(format!("{} must be greater than 0, not {}", name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// what `maple run INPUT` points at
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// as given on the command line, recordings keep it to open the project again
+    pub input: String,
+    pub name: String,
+    /// `require` looks for modules here
+    pub root: PathBuf,
+    pub entry_file: PathBuf,
+    pub manifest: Manifest,
+}
+
+impl Project {
+    /// a lua file is the entry of the project in its directory. a directory uses
+    /// the entry named by its `maple.toml`, or its `main.lua`
+    pub fn open(input: &str) -> Result<Project, Box<dyn Error>> {
+        let path = PathBuf::from(input);
+        let root = if path.is_dir() {
+            path.clone()
+        } else {
+            match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+                _ => PathBuf::from("."),
+            }
+        };
+
+        let manifest_path = root.join(MANIFEST_FILE);
+        let manifest = if manifest_path.is_file() {
+            Manifest::read(&manifest_path)?
+        } else {
+            Manifest::default()
+        };
+
+        let name = match &manifest.name {
+            Some(name) => name.clone(),
+            None => default_name(&path),
+        };
+
+        let entry_file = if path.is_dir() {
+            let entry = manifest.entry.as_deref().unwrap_or(MAIN_FILE);
+            root.join(entry)
+        } else {
+            path
+        };
+        if !entry_file.is_file() {
+            return Err(From::from(format!(
+                "{} not found, a project directory needs a {} or an entry in {}",
+                entry_file.display(),
+                MAIN_FILE,
+                MANIFEST_FILE
+            )));
+        }
+
+        Ok(Project {
+            input: String::from(input),
+            name,
+            root,
+            entry_file,
+            manifest,
+        })
+    }
+
+    pub fn title(&self) -> &str {
+        self.manifest.window.title.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// the directory name of a project directory, the file stem of a lua file
+pub fn default_name(path: &Path) -> String {
+    let name = if path.is_dir() {
+        fs::canonicalize(path).ok().and_then(|dir| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+    } else {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+    };
+    name.unwrap_or_else(|| String::from("maple"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_manifest_uses_defaults() {
+        let manifest: Manifest = toml::from_str("").unwrap();
+        assert_eq!(manifest.name, None);
+        assert_eq!(manifest.window.width, 800.0);
+        assert_eq!(manifest.window.height, 600.0);
+        assert_eq!(manifest.ui.font_size, 18.0);
+        assert_eq!(manifest.ui.panels, Panel::ALL.to_vec());
+        assert_eq!(manifest.runtime.tick_rate, None);
+        assert_eq!(manifest.runtime.history_length, None);
+        assert!(manifest.check().is_ok());
+    }
+
+    #[test]
+    fn partial_manifest_keeps_other_defaults() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+            [window]
+            width = 1024
+
+            [ui.layout.console]
+            size = [200, 100]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.window.width, 1024.0);
+        assert_eq!(manifest.window.height, 600.0);
+        assert!(manifest.window.vsync);
+        assert_eq!(
+            manifest.ui.layout(Panel::Console),
+            ([50.0, 660.0], [200.0, 100.0])
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert!(toml::from_str::<Manifest>("[window]\nwidht = 1024").is_err());
+    }
+
+    #[test]
+    fn sizes_must_be_positive() {
+        let manifest: Manifest = toml::from_str("[ui]\nfont_size = 0").unwrap();
+        assert!(manifest.check().is_err());
+        let manifest: Manifest = toml::from_str("[window]\nheight = -1").unwrap();
+        assert!(manifest.check().is_err());
+    }
+}
@@ -0,0 +1,505 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use ggez::event::KeyMods;
+use rlua::{Integer, Lua, Table, ToLua, Value};
+
+use crate::dump::{dump_value, DumpFormat};
+use crate::input::InputEvent;
+use crate::led::{led_button, LedEvent};
+use crate::selection::ItemPath;
+use crate::snapshot::StateValue;
+use crate::state::StateKey;
+
+const HEADER: &str = "-- maple recording, one `event { ... }` per recorded event\n";
+
+/// everything that has to be fed back to reproduce a run
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordEvent {
+    /// the first event, the canonical `maple run` input, the entry file it loaded and the rng
+    /// seed the recording was made with. `source` is `None` in recordings made before it was kept
+    Start {
+        script: String,
+        source: Option<String>,
+        seed: u64,
+    },
+    /// the scaled delta passed to `update`
+    Tick(f64),
+    /// a button of `mp_selection` or a window was clicked, `text` is only for reading the file
+    Select {
+        path: ItemPath,
+        text: String,
+    },
+    /// a widget of `mp_selection` or a window changed its value
+    SetValue {
+        path: ItemPath,
+        value: StateValue,
+    },
+    Input(InputEvent),
+    /// the `mp` commands queued so far ran here
+    Commands,
+    /// a cell of the led window, or of a led item when `path` is set, was clicked or hovered
+    Led {
+        path: Option<ItemPath>,
+        event: LedEvent,
+    },
+    /// a value of `mp_state` was edited in the status window
+    SetState {
+        path: Vec<StateKey>,
+        value: StateValue,
+    },
+    /// a line typed in the repl
+    Eval(String),
+    /// `mp_state` was replaced from the timeline or a snapshot
+    Restore(StateValue),
+    /// the entry file was reloaded with this content, modules are read from disk again
+    Reload(String),
+}
+
+fn event_error(message: String) -> rlua::Error {
+    rlua::Error::RuntimeError(format!("bad recorded event: {}", message))
+}
+
+/// `window` is left out for `mp_selection`, and `index` is a number unless the item
+/// is inside a layout item, so older recordings still read
+fn set_path(table: &Table, path: &ItemPath) -> rlua::Result<()> {
+    match path.indices.as_slice() {
+        [index] => table.set("index", *index)?,
+        indices => table.set("index", indices.to_vec())?,
+    }
+    table.set("window", path.window)
+}
+
+/// the led window has no item path, led items do
+fn get_led_path(table: &Table) -> rlua::Result<Option<ItemPath>> {
+    match table.get::<_, Value>("index")? {
+        Value::Nil => Ok(None),
+        _ => get_path(table).map(Some),
+    }
+}
+
+fn get_state_keys(table: &Table) -> rlua::Result<Vec<StateKey>> {
+    let mut keys = vec![];
+    for key in table.get::<_, Table>("path")?.sequence_values::<Value>() {
+        let key = key?;
+        match StateKey::from_value(&key) {
+            Some(key) => keys.push(key),
+            None => return Err(event_error(format!("bad state key {:?}", key))),
+        }
+    }
+    Ok(keys)
+}
+
+fn get_state<'lua>(
+    lua_ctx: rlua::Context<'lua>,
+    table: &Table<'lua>,
+    field: &str,
+) -> rlua::Result<StateValue> {
+    StateValue::capture(lua_ctx, table.get(field)?, &mut vec![])?
+        .ok_or_else(|| event_error(format!("missing {}", field)))
+}
+
+fn get_path(table: &Table) -> rlua::Result<ItemPath> {
+    let indices = match table.get::<_, Value>("index")? {
+        Value::Table(indices) => indices.sequence_values().collect::<rlua::Result<_>>()?,
+        Value::Integer(index) => vec![index as usize],
+        other => return Err(event_error(format!("bad item index {:?}", other))),
+    };
+    Ok(ItemPath {
+        window: table.get("window")?,
+        indices,
+    })
+}
+
+impl RecordEvent {
+    fn to_table<'lua>(&self, lua_ctx: rlua::Context<'lua>) -> rlua::Result<Table<'lua>> {
+        let table = lua_ctx.create_table()?;
+        match self {
+            RecordEvent::Start {
+                script,
+                source,
+                seed,
+            } => {
+                table.set("kind", "start")?;
+                table.set("script", script.as_str())?;
+                table.set("source", source.as_deref())?;
+                table.set("seed", *seed as Integer)?;
+            }
+            RecordEvent::Tick(delta) => {
+                table.set("kind", "tick")?;
+                table.set("delta", *delta)?;
+            }
+            RecordEvent::Select { path, text } => {
+                table.set("kind", "select")?;
+                set_path(&table, path)?;
+                table.set("text", text.as_str())?;
+            }
+            RecordEvent::SetValue { path, value } => {
+                table.set("kind", "set_value")?;
+                set_path(&table, path)?;
+                table.set("value", value.to_lua(lua_ctx)?)?;
+            }
+            RecordEvent::Commands => table.set("kind", "commands")?,
+            RecordEvent::Led { path, event } => {
+                table.set("kind", "led")?;
+                if let Some(path) = path {
+                    set_path(&table, path)?;
+                }
+                match event {
+                    LedEvent::Click { x, y, button } => {
+                        table.set("event", "click")?;
+                        table.set("x", *x)?;
+                        table.set("y", *y)?;
+                        table.set("button", *button)?;
+                    }
+                    LedEvent::Hover { x, y } => {
+                        table.set("event", "hover")?;
+                        table.set("x", *x)?;
+                        table.set("y", *y)?;
+                    }
+                }
+            }
+            RecordEvent::SetState { path, value } => {
+                table.set("kind", "set_state")?;
+                let keys = lua_ctx.create_table()?;
+                for (i, key) in path.iter().enumerate() {
+                    keys.set(i + 1, key)?;
+                }
+                table.set("path", keys)?;
+                table.set("value", value.to_lua(lua_ctx)?)?;
+            }
+            RecordEvent::Eval(code) => {
+                table.set("kind", "eval")?;
+                table.set("code", code.as_str())?;
+            }
+            RecordEvent::Restore(state) => {
+                table.set("kind", "restore")?;
+                table.set("state", state.to_lua(lua_ctx)?)?;
+            }
+            RecordEvent::Reload(source) => {
+                table.set("kind", "reload")?;
+                table.set("source", source.as_str())?;
+            }
+            RecordEvent::Input(input) => match input {
+                InputEvent::MouseMove {
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    captured,
+                } => {
+                    table.set("kind", "mouse_move")?;
+                    table.set("x", *x)?;
+                    table.set("y", *y)?;
+                    table.set("dx", *dx)?;
+                    table.set("dy", *dy)?;
+                    table.set("captured", *captured)?;
+                }
+                InputEvent::MouseDown { button, x, y } => {
+                    table.set("kind", "mouse_down")?;
+                    table.set("button", button.as_str())?;
+                    table.set("x", *x)?;
+                    table.set("y", *y)?;
+                }
+                InputEvent::MouseUp {
+                    button,
+                    x,
+                    y,
+                    captured,
+                } => {
+                    table.set("kind", "mouse_up")?;
+                    table.set("button", button.as_str())?;
+                    table.set("x", *x)?;
+                    table.set("y", *y)?;
+                    table.set("captured", *captured)?;
+                }
+                InputEvent::KeyDown { key, mods, repeat } => {
+                    table.set("kind", "key_down")?;
+                    table.set("key", key.as_str())?;
+                    table.set("mods", mods.bits())?;
+                    table.set("repeat", *repeat)?;
+                }
+                InputEvent::KeyUp {
+                    key,
+                    mods,
+                    captured,
+                } => {
+                    table.set("kind", "key_up")?;
+                    table.set("key", key.as_str())?;
+                    table.set("mods", mods.bits())?;
+                    table.set("captured", *captured)?;
+                }
+                InputEvent::Text(text) => {
+                    table.set("kind", "text")?;
+                    table.set("text", text.as_str())?;
+                }
+                InputEvent::Wheel { x, y } => {
+                    table.set("kind", "wheel")?;
+                    table.set("x", *x)?;
+                    table.set("y", *y)?;
+                }
+            },
+        }
+        Ok(table)
+    }
+
+    fn from_table<'lua>(
+        lua_ctx: rlua::Context<'lua>,
+        table: Table<'lua>,
+    ) -> rlua::Result<RecordEvent> {
+        let kind = table.get::<_, String>("kind")?;
+        let event = match kind.as_str() {
+            "start" => RecordEvent::Start {
+                script: table.get("script")?,
+                source: table.get("source")?,
+                seed: table.get::<_, Integer>("seed")? as u64,
+            },
+            "tick" => RecordEvent::Tick(table.get("delta")?),
+            "select" => RecordEvent::Select {
+                path: get_path(&table)?,
+                text: table.get::<_, Option<String>>("text")?.unwrap_or_default(),
+            },
+            "set_value" => RecordEvent::SetValue {
+                path: get_path(&table)?,
+                value: get_state(lua_ctx, &table, "value")?,
+            },
+            "commands" => RecordEvent::Commands,
+            "led" => {
+                let x = table.get("x")?;
+                let y = table.get("y")?;
+                let event = match table.get::<_, String>("event")?.as_str() {
+                    "click" => {
+                        let name = table.get::<_, String>("button")?;
+                        let button = led_button(&name)
+                            .ok_or_else(|| event_error(format!("unknown button '{}'", name)))?;
+                        LedEvent::Click { x, y, button }
+                    }
+                    "hover" => LedEvent::Hover { x, y },
+                    other => return Err(event_error(format!("unknown led event '{}'", other))),
+                };
+                RecordEvent::Led {
+                    path: get_led_path(&table)?,
+                    event,
+                }
+            }
+            "set_state" => RecordEvent::SetState {
+                path: get_state_keys(&table)?,
+                value: get_state(lua_ctx, &table, "value")?,
+            },
+            "eval" => RecordEvent::Eval(table.get("code")?),
+            "restore" => RecordEvent::Restore(get_state(lua_ctx, &table, "state")?),
+            "reload" => RecordEvent::Reload(table.get("source")?),
+            "mouse_move" => RecordEvent::Input(InputEvent::MouseMove {
+                x: table.get("x")?,
+                y: table.get("y")?,
+                dx: table.get("dx")?,
+                dy: table.get("dy")?,
+                captured: table.get("captured")?,
+            }),
+            "mouse_down" => RecordEvent::Input(InputEvent::MouseDown {
+                button: table.get("button")?,
+                x: table.get("x")?,
+                y: table.get("y")?,
+            }),
+            "mouse_up" => RecordEvent::Input(InputEvent::MouseUp {
+                button: table.get("button")?,
+                x: table.get("x")?,
+                y: table.get("y")?,
+                captured: table.get("captured")?,
+            }),
+            "key_down" => RecordEvent::Input(InputEvent::KeyDown {
+                key: table.get("key")?,
+                mods: KeyMods::from_bits_truncate(table.get("mods")?),
+                repeat: table.get("repeat")?,
+            }),
+            "key_up" => RecordEvent::Input(InputEvent::KeyUp {
+                key: table.get("key")?,
+                mods: KeyMods::from_bits_truncate(table.get("mods")?),
+                captured: table.get("captured")?,
+            }),
+            "text" => RecordEvent::Input(InputEvent::Text(table.get("text")?)),
+            "wheel" => RecordEvent::Input(InputEvent::Wheel {
+                x: table.get("x")?,
+                y: table.get("y")?,
+            }),
+            other => return Err(event_error(format!("unknown kind '{}'", other))),
+        };
+        Ok(event)
+    }
+}
+
+/// appends events to a `.mpr` file as they happen, so a crash keeps what led to it
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        let mut file = File::create(path)?;
+        file.write_all(HEADER.as_bytes())?;
+        Ok(Recorder { file })
+    }
+
+    pub fn write(&mut self, lua_ctx: rlua::Context, event: &RecordEvent) -> rlua::Result<()> {
+        let table = event.to_table(lua_ctx)?;
+        let text = dump_value(Value::Table(table), DumpFormat::Lua, 0)?;
+        self.file
+            .write_all(format!("event {}\n", text).as_bytes())
+            .map_err(rlua::Error::external)
+    }
+}
+
+/// read a `.mpr` file, it is lua calling `event` once per event
+pub fn read_recording(path: &Path) -> Result<Vec<RecordEvent>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    let events = Arc::new(Mutex::new(vec![]));
+    let lua = Lua::new();
+    lua.context(|lua_ctx| -> rlua::Result<()> {
+        let collected = events.clone();
+        let event = lua_ctx.create_function(move |lua_ctx, table: Table| {
+            collected
+                .lock()
+                .unwrap()
+                .push(RecordEvent::from_table(lua_ctx, table)?);
+            Ok(())
+        })?;
+        lua_ctx.globals().set("event", event)?;
+        lua_ctx
+            .load(&content)
+            .set_name(&format!("@{}", path.display()))?
+            .exec()
+    })?;
+    let events = events.lock().unwrap().drain(..).collect::<Vec<_>>();
+    match events.first() {
+        Some(RecordEvent::Start { .. }) => Ok(events),
+        _ => Err(From::from(format!(
+            "{} is not a maple recording, it has no start event",
+            path.display()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<RecordEvent> {
+        vec![
+            RecordEvent::Start {
+                script: String::from("/home/maple/example/single"),
+                source: Some(String::from(
+                    "function awake()\n  mp_state = { n = 1 }\nend\n",
+                )),
+                seed: 42,
+            },
+            RecordEvent::Tick(0.5),
+            RecordEvent::Select {
+                path: ItemPath::selection(2),
+                text: String::from("buy"),
+            },
+            RecordEvent::SetValue {
+                path: ItemPath {
+                    window: Some(1),
+                    indices: vec![3, 1, 2],
+                },
+                value: StateValue::Table(vec![
+                    (StateKey::Integer(1), StateValue::Integer(5)),
+                    (StateKey::Integer(2), StateValue::Number(1.5)),
+                ]),
+            },
+            RecordEvent::Input(InputEvent::KeyDown {
+                key: String::from("space"),
+                mods: KeyMods::SHIFT | KeyMods::CTRL,
+                repeat: false,
+            }),
+            RecordEvent::Commands,
+            RecordEvent::Led {
+                path: None,
+                event: LedEvent::Click {
+                    x: 1,
+                    y: 2,
+                    button: "right",
+                },
+            },
+            RecordEvent::Led {
+                path: Some(ItemPath {
+                    window: None,
+                    indices: vec![4, 1],
+                }),
+                event: LedEvent::Hover { x: 3, y: 4 },
+            },
+            RecordEvent::SetState {
+                path: vec![
+                    StateKey::String(String::from("items")),
+                    StateKey::Integer(2),
+                ],
+                value: StateValue::Str(String::from("sword")),
+            },
+            RecordEvent::Eval(String::from("mp_state.hp = 10")),
+            RecordEvent::Restore(StateValue::Table(vec![(
+                StateKey::String(String::from("hp")),
+                StateValue::Boolean(true),
+            )])),
+            RecordEvent::Reload(String::from("mp_state = {}\n")),
+        ]
+    }
+
+    #[test]
+    fn events_survive_a_lua_table() {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            for event in events() {
+                let table = event.to_table(lua_ctx).unwrap();
+                assert_eq!(RecordEvent::from_table(lua_ctx, table).unwrap(), event);
+            }
+        });
+    }
+
+    #[test]
+    fn events_survive_a_recording_file() {
+        let path = std::env::temp_dir().join(format!("maple_record_{}.mpr", std::process::id()));
+        let mut recorder = Recorder::create(&path).unwrap();
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            for event in events() {
+                recorder.write(lua_ctx, &event).unwrap();
+            }
+        });
+        drop(recorder);
+        let read = read_recording(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), events());
+    }
+
+    #[test]
+    fn a_recording_starts_with_start() {
+        let path = std::env::temp_dir().join(format!("maple_no_start_{}.mpr", std::process::id()));
+        fs::write(&path, "event { kind = \"commands\" }\n").unwrap();
+        let read = read_recording(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(read.is_err());
+    }
+
+    #[test]
+    fn older_recordings_start_without_source() {
+        let path = std::env::temp_dir().join(format!("maple_old_start_{}.mpr", std::process::id()));
+        fs::write(
+            &path,
+            "event { kind = \"start\", script = \"example/single\", seed = 7 }\n",
+        )
+        .unwrap();
+        let read = read_recording(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            read.unwrap(),
+            vec![RecordEvent::Start {
+                script: String::from("example/single"),
+                source: None,
+                seed: 7,
+            }]
+        );
+    }
+}
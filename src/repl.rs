@@ -0,0 +1,167 @@
+use imgui::{im_str, FocusedWidget, ImString, Key};
+
+use crate::lua::MpLua;
+
+const INPUT_CAPACITY: usize = 1024;
+
+/// the input line under the console, evaluates lua in the running state
+pub struct Repl {
+    input: ImString,
+    history: Vec<String>,
+    /// index into `history` while browsing it with up and down
+    history_pos: Option<usize>,
+    /// imgui keeps its own copy of the text while an input is active,
+    /// a new id makes it read `input` again after we replaced it
+    generation: u32,
+    refocus: bool,
+    /// the input had the keyboard last frame
+    active: bool,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Repl {
+            input: ImString::with_capacity(INPUT_CAPACITY),
+            history: vec![],
+            history_pos: None,
+            generation: 0,
+            refocus: false,
+            active: false,
+        }
+    }
+
+    /// keys are read before the input is drawn, the input has no completion or
+    /// history callback, and `set_input` gives it a new id and the focus back
+    pub fn render(&mut self, ui: &imgui::Ui, lua: &MpLua) {
+        if self.active {
+            if ui.is_key_pressed(ui.key_index(Key::UpArrow)) {
+                self.browse_history(true);
+            } else if ui.is_key_pressed(ui.key_index(Key::DownArrow)) {
+                self.browse_history(false);
+            } else if ui.is_key_pressed(ui.key_index(Key::Tab)) {
+                self.complete(lua);
+            }
+        }
+        if self.refocus {
+            ui.set_keyboard_focus_here(FocusedWidget::Next);
+            self.refocus = false;
+        }
+        let entered = ui
+            .input_text(&im_str!("lua##repl{}", self.generation), &mut self.input)
+            .enter_returns_true(true)
+            .build();
+        self.active = ui.is_item_active();
+        if entered {
+            self.submit(lua);
+        }
+    }
+
+    fn set_input(&mut self, text: &str) {
+        self.input.clear();
+        self.input.push_str(text);
+        self.generation = self.generation.wrapping_add(1);
+        self.refocus = true;
+    }
+
+    fn submit(&mut self, lua: &MpLua) {
+        let code = String::from(self.input.to_str().trim());
+        // enter drops the focus, keep typing in the same line
+        self.set_input("");
+        self.history_pos = None;
+        if code.is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&code) {
+            self.history.push(code.clone());
+        }
+        lua.run_repl(&code);
+    }
+
+    fn browse_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        self.history_pos = match (self.history_pos, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (Some(pos), true) => Some(pos.saturating_sub(1)),
+            (Some(pos), false) if pos + 1 < self.history.len() => Some(pos + 1),
+            _ => None,
+        };
+        let text = match self.history_pos {
+            Some(pos) => self.history[pos].clone(),
+            None => String::new(),
+        };
+        self.set_input(&text);
+    }
+
+    /// completes the word before the end of the line, a single match replaces it,
+    /// several extend it to their common prefix and are listed in the console
+    fn complete(&mut self, lua: &MpLua) {
+        let text = String::from(self.input.to_str());
+        let (head, word) = text.split_at(word_start(&text));
+        let names = match lua.complete(word) {
+            Ok(names) => names,
+            Err(e) => {
+                lua.report_error(&e);
+                return;
+            }
+        };
+        let completed = match names.len() {
+            0 => return,
+            1 => names[0].clone(),
+            _ => {
+                lua.console_log(names.join("  "));
+                common_prefix(&names)
+            }
+        };
+        if completed != word {
+            self.set_input(&format!("{}{}", head, completed));
+        }
+    }
+}
+
+/// the byte offset of the name being typed at the end of `text`, a dotted path counts as one
+fn word_start(text: &str) -> usize {
+    text.char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_' || *c == '.'))
+        .map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+fn common_prefix(names: &[String]) -> String {
+    let mut prefix = names[0].as_str();
+    for name in &names[1..] {
+        while !name.starts_with(prefix) {
+            let mut chars = prefix.chars();
+            chars.next_back();
+            prefix = chars.as_str();
+        }
+    }
+    String::from(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_start_skips_to_the_name() {
+        assert_eq!(word_start("mp_state.go"), 0);
+        assert_eq!(word_start("print(mp.ra"), 6);
+        assert_eq!(word_start("x = "), 4);
+    }
+
+    #[test]
+    fn word_start_after_multibyte_characters() {
+        let text = "print（“mp.ra";
+        assert_eq!(&text[word_start(text)..], "mp.ra");
+        let text = "x　y";
+        assert_eq!(&text[word_start(text)..], "y");
+    }
+
+    #[test]
+    fn common_prefix_of_names() {
+        let names = vec![String::from("mp_state"), String::from("mp_selection")];
+        assert_eq!(common_prefix(&names), "mp_s");
+    }
+}
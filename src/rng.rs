@@ -0,0 +1,176 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rlua::{Integer, Table, Value};
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+/// pcg32, small and the same on every platform, so a seed always replays the same run
+pub struct Rng {
+    state: u64,
+    seed: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng { state: 0, seed };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// `mp.seed`, the run continues as if it had started with `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Rng::new(seed);
+    }
+
+    /// a seed that fits in a few digits, so it is easy to copy from the ui
+    pub fn time_seed() -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        (u64::from(now.subsec_nanos()) ^ now.as_secs()) % 1_000_000
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        (u64::from(self.next_u32()) << 32) | u64::from(self.next_u32())
+    }
+
+    /// in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// in `[0, n)` without modulo bias, `n` must not be 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let x = self.next_u64();
+            if x >= threshold {
+                return x % n;
+            }
+        }
+    }
+
+    /// in `[a, b]`
+    pub fn range(&mut self, a: Integer, b: Integer) -> Integer {
+        let span = (b.wrapping_sub(a) as u64).wrapping_add(1);
+        if span == 0 {
+            // the whole integer range
+            return self.next_u64() as Integer;
+        }
+        a.wrapping_add(self.below(span) as Integer)
+    }
+}
+
+fn empty_table_error(function: &str) -> rlua::Error {
+    rlua::Error::RuntimeError(format!("mp.{} needs a non-empty sequence", function))
+}
+
+/// a random value of a sequence
+pub fn choice<'lua>(rng: &mut Rng, table: Table<'lua>) -> rlua::Result<Value<'lua>> {
+    let len = table.len()?;
+    if len < 1 {
+        return Err(empty_table_error("choice"));
+    }
+    table.get(rng.range(1, len))
+}
+
+/// `{ { value, weight }, ... }`, a sequence so the result doesn't depend on `pairs` order
+pub fn weighted_choice<'lua>(rng: &mut Rng, table: Table<'lua>) -> rlua::Result<Value<'lua>> {
+    let mut entries = vec![];
+    let mut total = 0.0;
+    for entry in table.sequence_values::<Table>() {
+        let entry = entry?;
+        let weight = entry.get::<_, f64>(2)?;
+        if weight < 0.0 {
+            return Err(rlua::Error::RuntimeError(String::from(
+                "mp.weighted_choice weights can't be negative",
+            )));
+        }
+        total += weight;
+        entries.push((entry.get::<_, Value>(1)?, weight));
+    }
+    if entries.is_empty() || total <= 0.0 {
+        return Err(empty_table_error("weighted_choice"));
+    }
+    let mut pick = rng.next_f64() * total;
+    for (value, weight) in &entries {
+        if pick < *weight {
+            return Ok(value.clone());
+        }
+        pick -= weight;
+    }
+    // rounding can leave `pick` just above the last weight
+    Ok(entries
+        .into_iter()
+        .rev()
+        .find(|(_, weight)| *weight > 0.0)
+        .map(|(value, _)| value)
+        .unwrap_or(Value::Nil))
+}
+
+/// fisher-yates in place
+pub fn shuffle(rng: &mut Rng, table: &Table) -> rlua::Result<()> {
+    let len = table.len()?;
+    for i in (2..=len).rev() {
+        let j = rng.range(1, i);
+        if i != j {
+            let a = table.get::<_, Value>(i)?;
+            let b = table.get::<_, Value>(j)?;
+            table.set(i, b)?;
+            table.set(j, a)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_seed_gives_a_fixed_sequence() {
+        let mut rng = Rng::new(42);
+        let values = (0..4).map(|_| rng.next_u32()).collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![0xc2f5_7bd6, 0x6b07_c4a9, 0x72b7_b29b, 0x4421_5383]
+        );
+    }
+
+    #[test]
+    fn range_includes_both_ends() {
+        let mut rng = Rng::new(7);
+        let mut seen = [false; 3];
+        for _ in 0..100 {
+            let value = rng.range(-1, 1);
+            assert!((-1..=1).contains(&value));
+            seen[(value + 1) as usize] = true;
+        }
+        assert_eq!(seen, [true; 3]);
+        rng.range(Integer::MIN, Integer::MAX);
+    }
+
+    #[test]
+    fn next_f64_is_below_one() {
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            let value = rng.next_f64();
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+}
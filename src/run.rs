@@ -1,18 +1,53 @@
+use std::collections::VecDeque;
 use std::error::Error;
+use std::path::Path;
 
+use crate::host::HostCommand;
+use crate::input::{key_name, mouse_button_name, InputEvent};
 use crate::lua::MpLua;
+use crate::project::Project;
+use crate::record::{read_recording, RecordEvent};
 use ggez::conf;
 use ggez::event::{self, EventHandler, KeyCode, KeyMods, MouseButton};
 use ggez::graphics;
+use ggez::timer;
 use ggez::{Context, GameResult};
 
 use crate::imgui_wrapper::ImGuiWrapper;
 
-pub fn run(input_path: &str) -> Result<(), Box<dyn Error>> {
+/// a frame slower than this many ticks drops the rest, instead of spiraling
+/// into longer and longer frames trying to catch up
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+pub struct RunOptions {
+    /// write a `.mpr` recording of the session
+    pub record: Option<String>,
+    /// a random seed when not set
+    pub seed: Option<u64>,
+}
+
+pub fn run(input_path: &str, options: &RunOptions) -> Result<(), Box<dyn Error>> {
     // let file_content = fs::read_to_string(&input_path)?;
-    let mut mp_lua = MpLua::new(String::from(input_path));
+    let project = Project::open(input_path)?;
+    let mut mp_lua = match options.seed {
+        Some(seed) => MpLua::with_seed(project, seed)?,
+        None => MpLua::new(project)?,
+    };
+    if let Some(record_path) = &options.record {
+        mp_lua.start_recording(Path::new(record_path))?;
+    }
     mp_lua.awake()?;
-    ggez_main(mp_lua)?;
+    ggez_main(mp_lua, None)?;
+    Ok(())
+}
+
+/// play a `.mpr` recording in a window, live input is ignored until it ends
+pub fn replay(record_path: &str) -> Result<(), Box<dyn Error>> {
+    let events = read_recording(Path::new(record_path))?;
+    let mut mp_lua = MpLua::from_recording(&events[0])?;
+    mp_lua.awake()?;
+    mp_lua.set_replaying(true);
+    ggez_main(mp_lua, Some(events.into()))?;
     Ok(())
 }
 
@@ -20,27 +55,86 @@ struct MainState {
     imgui_wrapper: ImGuiWrapper,
     hidpi_factor: f32,
     lua: MpLua,
+    replay: Option<VecDeque<RecordEvent>>,
 }
 
 impl MainState {
-    fn new(mut ctx: &mut Context, hidpi_factor: f32, lua: MpLua) -> GameResult<MainState> {
-        let imgui_wrapper = ImGuiWrapper::new(&mut ctx);
+    fn new(
+        mut ctx: &mut Context,
+        hidpi_factor: f32,
+        lua: MpLua,
+        replay: Option<VecDeque<RecordEvent>>,
+    ) -> GameResult<MainState> {
+        let imgui_wrapper = ImGuiWrapper::new(&mut ctx, lua.project().manifest.ui.clone());
         let s = MainState {
             imgui_wrapper,
             hidpi_factor,
             lua,
+            replay,
         };
         Ok(s)
     }
+
+    fn handle_input(&mut self, event: InputEvent) {
+        // a replay only stays deterministic without live input
+        if self.replay.is_some() {
+            return;
+        }
+        self.lua.log_result(&self.lua.handle_input(event));
+    }
+
+    fn handle_host_commands(&mut self, ctx: &mut Context, commands: Vec<HostCommand>) {
+        for command in commands {
+            match command {
+                HostCommand::Quit => event::quit(ctx),
+                HostCommand::SetTitle(title) => graphics::set_window_title(ctx, &title),
+            }
+        }
+    }
+
+    /// recorded events replace the ticks and commands of a live run
+    fn update_replay(&mut self, ctx: &mut Context) {
+        let tick_rate = self.lua.tick_rate();
+        while timer::check_update_time(ctx, tick_rate) {
+            let result = match &mut self.replay {
+                Some(events) => self.lua.replay_frame(events),
+                None => return,
+            };
+            match result {
+                Ok(commands) => self.handle_host_commands(ctx, commands),
+                Err(e) => self.lua.report_error(&e),
+            }
+            if let Some(events) = &self.replay {
+                if events.is_empty() {
+                    self.replay = None;
+                    self.lua.set_replaying(false);
+                    self.lua.console_log(String::from("replay finished"));
+                }
+            }
+        }
+    }
 }
 
 impl EventHandler for MainState {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
-        match self.lua.tick_signal() {
-            Ok(_) => {}
-            Err(e) => {
-                println!("ticksignal: {}", e);
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.lua.tick_reload();
+        if self.replay.is_some() {
+            self.update_replay(ctx);
+            return Ok(());
+        }
+        // fixed rate, so the logic doesn't depend on the frame rate
+        let tick_rate = self.lua.tick_rate();
+        let tick_delta = 1.0 / f64::from(tick_rate);
+        let mut ticks = 0;
+        while timer::check_update_time(ctx, tick_rate) {
+            if ticks < MAX_TICKS_PER_FRAME {
+                self.lua.log_result(&self.lua.tick(tick_delta));
+                ticks += 1;
             }
+        }
+        match self.lua.tick_commands() {
+            Ok(commands) => self.handle_host_commands(ctx, commands),
+            Err(e) => self.lua.report_error(&e),
         };
         Ok(())
     }
@@ -50,11 +144,21 @@ impl EventHandler for MainState {
 
         // Render game stuff
         {
-            // graphics::draw(ctx, &circle, (na::Point2::new(0.0, 0.0),))?;
+            match self.lua.build_show() {
+                Ok(show) => {
+                    if let Err(e) = show.draw(ctx) {
+                        self.lua
+                            .report_error(&rlua::Error::RuntimeError(format!("mp_show: {}", e)));
+                    }
+                }
+                Err(e) => self.lua.report_error(&e),
+            }
         }
 
         // Render game ui
         {
+            let refreshed = self.lua.refresh_ui_selection();
+            self.lua.log_result(&refreshed);
             self.imgui_wrapper.render(ctx, self.hidpi_factor, &self.lua);
         }
 
@@ -62,32 +166,41 @@ impl EventHandler for MainState {
         Ok(())
     }
 
-    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, dx: f32, dy: f32) {
         self.imgui_wrapper.update_mouse_pos(x, y);
+        let captured = self.imgui_wrapper.want_capture_mouse();
+        self.handle_input(InputEvent::MouseMove {
+            x,
+            y,
+            dx,
+            dy,
+            captured,
+        });
     }
 
-    fn mouse_button_down_event(
-        &mut self,
-        _ctx: &mut Context,
-        button: MouseButton,
-        _x: f32,
-        _y: f32,
-    ) {
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
         self.imgui_wrapper.update_mouse_down((
             button == MouseButton::Left,
             button == MouseButton::Right,
             button == MouseButton::Middle,
         ));
+        if !self.imgui_wrapper.want_capture_mouse() {
+            let button = String::from(mouse_button_name(button));
+            self.handle_input(InputEvent::MouseDown { button, x, y });
+        }
     }
 
-    fn mouse_button_up_event(
-        &mut self,
-        _ctx: &mut Context,
-        _button: MouseButton,
-        _x: f32,
-        _y: f32,
-    ) {
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
         self.imgui_wrapper.update_mouse_down((false, false, false));
+        // always released, so a button pressed outside imgui can't get stuck
+        let button = String::from(mouse_button_name(button));
+        let captured = self.imgui_wrapper.want_capture_mouse();
+        self.handle_input(InputEvent::MouseUp {
+            button,
+            x,
+            y,
+            captured,
+        });
     }
 
     fn key_down_event(
@@ -95,17 +208,37 @@ impl EventHandler for MainState {
         _ctx: &mut Context,
         keycode: KeyCode,
         keymods: KeyMods,
-        _repeat: bool,
+        repeat: bool,
     ) {
         self.imgui_wrapper.update_key_down(keycode, keymods);
+        // clock hotkeys are not passed to lua, and work while imgui has focus
+        if self.lua.handle_clock_key(keycode) {
+            return;
+        }
+        if !self.imgui_wrapper.want_capture_keyboard() {
+            self.handle_input(InputEvent::KeyDown {
+                key: String::from(key_name(keycode)),
+                mods: keymods,
+                repeat,
+            });
+        }
     }
 
     fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, keymods: KeyMods) {
         self.imgui_wrapper.update_key_up(keycode, keymods);
+        let captured = self.imgui_wrapper.want_capture_keyboard();
+        self.handle_input(InputEvent::KeyUp {
+            key: String::from(key_name(keycode)),
+            mods: keymods,
+            captured,
+        });
     }
 
     fn text_input_event(&mut self, _ctx: &mut Context, val: char) {
         self.imgui_wrapper.update_text(val);
+        if !self.imgui_wrapper.want_capture_keyboard() {
+            self.handle_input(InputEvent::Text(val.to_string()));
+        }
     }
 
     fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
@@ -116,20 +249,31 @@ impl EventHandler for MainState {
 
     fn mouse_wheel_event(&mut self, _ctx: &mut Context, x: f32, y: f32) {
         self.imgui_wrapper.update_scroll(x, y);
+        if !self.imgui_wrapper.want_capture_mouse() {
+            self.handle_input(InputEvent::Wheel { x, y });
+        }
     }
 }
 
-pub fn ggez_main(mp_lua: MpLua) -> ggez::GameResult {
-    let cb = ggez::ContextBuilder::new("super_simple with imgui", "ggez")
-        .window_setup(conf::WindowSetup::default().title("super_simple with imgui"))
+pub fn ggez_main(mp_lua: MpLua, replay: Option<VecDeque<RecordEvent>>) -> ggez::GameResult {
+    let project = mp_lua.project();
+    let window = &project.manifest.window;
+    let cb = ggez::ContextBuilder::new(&project.name, "maple")
+        .window_setup(
+            conf::WindowSetup::default()
+                .title(project.title())
+                .vsync(window.vsync),
+        )
         .window_mode(
-            conf::WindowMode::default().resizable(true), /*.dimensions(750.0, 500.0)*/
+            conf::WindowMode::default()
+                .dimensions(window.width, window.height)
+                .resizable(window.resizable),
         );
     let (ref mut ctx, event_loop) = &mut cb.build()?;
 
     let hidpi_factor = event_loop.get_primary_monitor().get_hidpi_factor() as f32;
 
-    let state = &mut MainState::new(ctx, hidpi_factor, mp_lua)?;
+    let state = &mut MainState::new(ctx, hidpi_factor, mp_lua, replay)?;
 
     event::run(ctx, event_loop, state)
 }
@@ -0,0 +1,974 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::ptr;
+
+use imgui::{
+    im_str, sys, ChildWindow, CollapsingHeader, ColorEdit, ComboBox, Drag, ImString, Slider,
+    StyleVar, WindowHoveredFlags,
+};
+use rlua::{Function, Integer, Table, Value};
+
+use crate::led::LedInput;
+use crate::lua::MpLua;
+use crate::show::read_size;
+use crate::state::{parse_state_path, StateKey};
+use crate::status::{display_value, INPUT_TEXT_CAPACITY};
+
+const DEFAULT_CANVAS_SIZE: [f32; 2] = [200.0, 200.0];
+const DEFAULT_CHILD_SIZE: [f32; 2] = [0.0, 200.0];
+const DISABLED_ALPHA: f32 = 0.4;
+/// registry name of the table giving each item table its default imgui id
+const ITEM_IDS: &str = "mp_item_ids";
+
+/// where the table of an item lives, its callback and value are looked up there when used
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemPath {
+    /// `mp_windows[window].content`, `mp_selection` when not set
+    pub window: Option<usize>,
+    /// the index in the list, then in the `content` of each layout item on the way
+    pub indices: Vec<usize>,
+}
+
+impl ItemPath {
+    pub fn selection(index: usize) -> Self {
+        ItemPath {
+            window: None,
+            indices: vec![index],
+        }
+    }
+
+    fn root(window: Option<usize>) -> Self {
+        ItemPath {
+            window,
+            indices: vec![],
+        }
+    }
+
+    fn child(&self, index: usize) -> Self {
+        let mut path = self.clone();
+        path.indices.push(index);
+        path
+    }
+
+    pub fn table<'lua>(&self, lua_ctx: rlua::Context<'lua>) -> rlua::Result<Table<'lua>> {
+        let globals = lua_ctx.globals();
+        let mut items = match self.window {
+            Some(window) => globals
+                .get::<_, Table>("mp_windows")?
+                .get::<_, Table>(window)?
+                .get::<_, Table>("content")?,
+            None => globals.get::<_, Table>("mp_selection")?,
+        };
+        for (i, index) in self.indices.iter().enumerate() {
+            let item = items.get::<_, Table>(*index)?;
+            if i + 1 == self.indices.len() {
+                return Ok(item);
+            }
+            items = item.get("content")?;
+        }
+        Ok(items)
+    }
+}
+
+impl fmt::Display for ItemPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.window {
+            Some(window) => write!(f, "mp_windows[{}].content", window)?,
+            None => write!(f, "mp_selection")?,
+        }
+        for (i, index) in self.indices.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".content")?;
+            }
+            write!(f, "[{}]", index)?;
+        }
+        Ok(())
+    }
+}
+
+/// the fields of an item that are read every frame, each may be a value
+/// or a function returning it, e.g. `text = function() return "buy ("..cost..")" end`
+pub struct ItemProps {
+    /// the imgui id of the widget, see `item_identity` for the default. an item
+    /// keeps its state, e.g. an open header, when items are inserted before it
+    pub id: Option<String>,
+    pub text: Option<String>,
+    pub enabled: bool,
+    pub visible: bool,
+    pub tooltip: Option<String>,
+}
+
+impl Default for ItemProps {
+    fn default() -> Self {
+        ItemProps {
+            id: None,
+            text: None,
+            enabled: true,
+            visible: true,
+            tooltip: None,
+        }
+    }
+}
+
+fn dynamic_field<'lua>(item: &Table<'lua>, key: &str) -> rlua::Result<Value<'lua>> {
+    match item.get::<_, Value>(key)? {
+        Value::Function(function) => function.call(()),
+        value => Ok(value),
+    }
+}
+
+fn dynamic_text(item: &Table, key: &str) -> rlua::Result<Option<String>> {
+    Ok(match dynamic_field(item, key)? {
+        Value::Nil => None,
+        value => Some(display_value(&value)),
+    })
+}
+
+/// a missing field counts as true, so both fields can be left out. a value, or
+/// what a function returns, is true unless it is `nil` or `false`, as in lua
+fn dynamic_flag(item: &Table, key: &str) -> rlua::Result<bool> {
+    let flag = match item.get::<_, Value>(key)? {
+        Value::Nil => return Ok(true),
+        Value::Function(function) => function.call::<_, Value>(())?,
+        value => value,
+    };
+    Ok(!matches!(flag, Value::Nil | Value::Boolean(false)))
+}
+
+/// a number that stays with an item table for as long as the table lives, so an
+/// item without `id` keeps its imgui id when it moves in its list
+pub fn item_identity<'lua>(
+    lua_ctx: rlua::Context<'lua>,
+    item: &Table<'lua>,
+) -> rlua::Result<Integer> {
+    let ids = match lua_ctx.named_registry_value::<_, Option<Table>>(ITEM_IDS)? {
+        Some(ids) => ids,
+        None => {
+            let ids = lua_ctx.create_table()?;
+            // weak keys, the items that are gone don't stay in it
+            let meta = lua_ctx.create_table()?;
+            meta.set("__mode", "k")?;
+            ids.set_metatable(Some(meta));
+            lua_ctx.set_named_registry_value(ITEM_IDS, ids.clone())?;
+            ids
+        }
+    };
+    if let Some(id) = ids.raw_get::<_, Option<Integer>>(item.clone())? {
+        return Ok(id);
+    }
+    let id = ids.raw_get::<_, Option<Integer>>("count")?.unwrap_or(0) + 1;
+    ids.raw_set("count", id)?;
+    ids.raw_set(item.clone(), id)?;
+    Ok(id)
+}
+
+pub fn read_props(item: &Table) -> rlua::Result<ItemProps> {
+    Ok(ItemProps {
+        id: dynamic_text(item, "id")?,
+        text: dynamic_text(item, "text")?,
+        enabled: dynamic_flag(item, "enabled")?,
+        visible: dynamic_flag(item, "visible")?,
+        tooltip: dynamic_text(item, "tooltip")?,
+    })
+}
+
+/// a tab of a `tab_bar`
+pub struct UiTab {
+    label: ImString,
+    items: Vec<UiSelectionItem>,
+}
+
+pub enum UiSelectionItem {
+    Button {
+        path: ItemPath,
+        text: String,
+    },
+    SliderInt {
+        path: ItemPath,
+        text: String,
+        min: i32,
+        max: i32,
+    },
+    SliderFloat {
+        path: ItemPath,
+        text: String,
+        min: f32,
+        max: f32,
+    },
+    DragFloat {
+        path: ItemPath,
+        text: String,
+        speed: f32,
+        min: f32,
+        max: f32,
+    },
+    Checkbox {
+        path: ItemPath,
+        text: String,
+    },
+    InputText {
+        path: ItemPath,
+        text: String,
+    },
+    Combo {
+        path: ItemPath,
+        text: String,
+        options: Vec<ImString>,
+    },
+    Radio {
+        path: ItemPath,
+        text: String,
+        options: Vec<ImString>,
+    },
+    ColorEdit {
+        path: ItemPath,
+        text: String,
+    },
+    /// a line of text, followed by the value at `bind` in `mp_state` if set
+    Text {
+        path: ItemPath,
+        text: String,
+        bind: Option<Vec<StateKey>>,
+    },
+    /// cells of the `led` field, in the same format as `mp_led`
+    Led {
+        path: ItemPath,
+        input: RefCell<LedInput>,
+    },
+    /// shapes of the `shapes` field, in the same format as `mp_show`
+    Canvas {
+        path: ItemPath,
+        size: [f32; 2],
+    },
+    /// the next item goes on the same line
+    SameLine,
+    Separator,
+    Spacing,
+    CollapsingHeader {
+        path: ItemPath,
+        text: String,
+        open: bool,
+        items: Vec<UiSelectionItem>,
+    },
+    /// the `content` of a tab bar is `tab_item`s, each with its own `content`
+    TabBar {
+        path: ItemPath,
+        tabs: Vec<UiTab>,
+    },
+    /// a scrolling region, a width or height of 0 fills the window
+    Child {
+        path: ItemPath,
+        size: [f32; 2],
+        border: bool,
+        items: Vec<UiSelectionItem>,
+    },
+    /// each item of `content` goes in the next column, wrapping to a new row
+    Columns {
+        path: ItemPath,
+        count: i32,
+        border: bool,
+        items: Vec<UiSelectionItem>,
+    },
+    Indent {
+        path: ItemPath,
+        width: Option<f32>,
+        items: Vec<UiSelectionItem>,
+    },
+}
+
+fn get_or<'lua, T: rlua::FromLua<'lua>>(
+    table: &Table<'lua>,
+    key: &str,
+    default: T,
+) -> rlua::Result<T> {
+    Ok(table.get::<_, Option<T>>(key)?.unwrap_or(default))
+}
+
+/// a `text` function is called every frame instead, see `read_props`
+fn get_text(table: &Table) -> rlua::Result<String> {
+    match table.get::<_, Value>("text")? {
+        Value::Function(_) => Ok(String::new()),
+        _ => table.get("text"),
+    }
+}
+
+fn get_options(table: &Table) -> rlua::Result<Vec<ImString>> {
+    let options = table.get::<_, Vec<String>>("options")?;
+    Ok(options.into_iter().map(ImString::new).collect())
+}
+
+/// the items of a list, `path` is the path of the list itself
+fn build_items(path: &ItemPath, lua_table: Table) -> rlua::Result<Vec<UiSelectionItem>> {
+    let mut items = vec![];
+    for pair in lua_table.pairs::<Integer, Table>() {
+        let (index, item_table) = pair?;
+        items.push(UiSelectionItem::build(
+            path.child(index as usize),
+            item_table,
+        )?);
+    }
+    Ok(items)
+}
+
+/// the `content` of a layout item, it may be left out
+fn build_content(path: &ItemPath, table: &Table) -> rlua::Result<Vec<UiSelectionItem>> {
+    match table.get::<_, Option<Table>>("content")? {
+        Some(content) => build_items(path, content),
+        None => Ok(vec![]),
+    }
+}
+
+/// appends `items` and each item table with its index and `type`, going into
+/// `content`, and `false` closing the list. compared by identity, an item or list
+/// that is replaced, added or removed changes it, edits inside an item that keeps
+/// its `type` don't
+pub fn write_signature<'lua>(
+    items: &Table<'lua>,
+    signature: &mut Vec<Value<'lua>>,
+) -> rlua::Result<()> {
+    signature.push(Value::Table(items.clone()));
+    for pair in items.clone().pairs::<Integer, Table>() {
+        let (index, item) = pair?;
+        signature.push(Value::Integer(index));
+        signature.push(Value::Table(item.clone()));
+        // a button has no `type`, the signature can't hold a nil
+        match item.get::<_, Value>("type")? {
+            Value::Nil => signature.push(Value::Boolean(true)),
+            widget_type => signature.push(widget_type),
+        }
+        if let Some(content) = item.get::<_, Option<Table>>("content")? {
+            write_signature(&content, signature)?;
+        }
+    }
+    signature.push(Value::Boolean(false));
+    Ok(())
+}
+
+/// `built` holds an earlier signature as a sequence, tables are compared by identity
+pub fn same_signature<'lua>(
+    lua_ctx: rlua::Context<'lua>,
+    built: &Table<'lua>,
+    signature: Vec<Value<'lua>>,
+) -> rlua::Result<bool> {
+    if built.len()? != signature.len() as Integer {
+        return Ok(false);
+    }
+    let rawequal = lua_ctx.globals().get::<_, Function>("rawequal")?;
+    for (i, value) in signature.into_iter().enumerate() {
+        let old = built.raw_get::<_, Value>(i + 1)?;
+        if !rawequal.call::<_, bool>((old, value))? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn build_tabs(path: &ItemPath, table: &Table) -> rlua::Result<Vec<UiTab>> {
+    let mut tabs = vec![];
+    if let Some(content) = table.get::<_, Option<Table>>("content")? {
+        for pair in content.pairs::<Integer, Table>() {
+            let (index, tab_table) = pair?;
+            let tab_path = path.child(index as usize);
+            if tab_table.get::<_, Option<String>>("type")?.as_deref() != Some("tab_item") {
+                return Err(rlua::Error::RuntimeError(format!(
+                    "{}: a tab_bar only holds tab_item",
+                    tab_path
+                )));
+            }
+            let text = tab_table.get::<_, String>("text")?;
+            tabs.push(UiTab {
+                label: im_str!("{}##{}", text, index),
+                items: build_content(&tab_path, &tab_table)?,
+            });
+        }
+    }
+    Ok(tabs)
+}
+
+fn render_items(items: &[UiSelectionItem], ui: &imgui::Ui, lua: &MpLua, enabled: bool) {
+    for item in items {
+        lua.log_result(&item.render(ui, lua, enabled));
+    }
+}
+
+fn show_tooltip(ui: &imgui::Ui, props: &ItemProps) {
+    if let Some(tooltip) = &props.tooltip {
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tooltip);
+        }
+    }
+}
+
+/// layout items without a header show their tooltip over the area they take,
+/// except over the items inside, which have tooltips of their own
+fn show_area_tooltip(ui: &imgui::Ui, props: &ItemProps, min: [f32; 2], max: [f32; 2]) {
+    if let Some(tooltip) = &props.tooltip {
+        if ui.is_window_hovered_with_flags(WindowHoveredFlags::CHILD_WINDOWS)
+            && ui.is_mouse_hovering_rect(min, max)
+            && !ui.is_any_item_hovered()
+        {
+            ui.tooltip_text(tooltip);
+        }
+    }
+}
+
+/// imgui-rs 0.4 has no tab bar, so this calls dear imgui directly. imgui-sys 0.4.0 has
+/// `igBeginTabBar(str_id, flags) -> bool` and `igBeginTabItem(label, p_open, flags) -> bool`,
+/// a null `p_open` leaves out the close button and `0` is the `None` flags.
+/// `End*` is only called when `Begin*` returned true, as dear imgui expects.
+/// the tooltip of the tab bar shows over each of its tabs
+fn render_tab_bar(ui: &imgui::Ui, tabs: &[UiTab], lua: &MpLua, props: &ItemProps, enabled: bool) {
+    let id = im_str!("##tab_bar");
+    if !unsafe { sys::igBeginTabBar(id.as_ptr(), 0) } {
+        return;
+    }
+    for tab in tabs {
+        let is_open = unsafe { sys::igBeginTabItem(tab.label.as_ptr(), ptr::null_mut(), 0) };
+        show_tooltip(ui, props);
+        if is_open {
+            render_items(&tab.items, ui, lua, enabled);
+            unsafe { sys::igEndTabItem() };
+        }
+    }
+    unsafe { sys::igEndTabBar() };
+}
+
+impl UiSelectionItem {
+    fn build(path: ItemPath, table: Table) -> rlua::Result<UiSelectionItem> {
+        let widget_type = table.get::<_, Option<String>>("type")?;
+        match widget_type.as_deref() {
+            Some("led") => {
+                return Ok(UiSelectionItem::Led {
+                    path,
+                    input: RefCell::new(LedInput::default()),
+                })
+            }
+            Some("canvas") => {
+                let size = match table.get::<_, Option<Table>>("size")? {
+                    Some(size) => read_size(&size)?,
+                    None => DEFAULT_CANVAS_SIZE,
+                };
+                return Ok(UiSelectionItem::Canvas { path, size });
+            }
+            Some("same_line") => return Ok(UiSelectionItem::SameLine),
+            Some("separator") => return Ok(UiSelectionItem::Separator),
+            Some("spacing") => return Ok(UiSelectionItem::Spacing),
+            Some("tab_bar") => {
+                let tabs = build_tabs(&path, &table)?;
+                return Ok(UiSelectionItem::TabBar { path, tabs });
+            }
+            Some("tab_item") => {
+                return Err(rlua::Error::RuntimeError(format!(
+                    "{}: tab_item only goes in the content of a tab_bar",
+                    path
+                )))
+            }
+            Some("child") => {
+                let size = match table.get::<_, Option<Table>>("size")? {
+                    Some(size) => read_size(&size)?,
+                    None => DEFAULT_CHILD_SIZE,
+                };
+                return Ok(UiSelectionItem::Child {
+                    size,
+                    border: get_or(&table, "border", true)?,
+                    items: build_content(&path, &table)?,
+                    path,
+                });
+            }
+            Some("columns") => {
+                return Ok(UiSelectionItem::Columns {
+                    count: get_or(&table, "count", 2)?.max(1),
+                    border: get_or(&table, "border", false)?,
+                    items: build_content(&path, &table)?,
+                    path,
+                })
+            }
+            Some("indent") => {
+                return Ok(UiSelectionItem::Indent {
+                    width: table.get("width")?,
+                    items: build_content(&path, &table)?,
+                    path,
+                })
+            }
+            _ => {}
+        }
+        let text = get_text(&table)?;
+        let item = match widget_type.as_deref() {
+            None | Some("button") => UiSelectionItem::Button { path, text },
+            Some("text") => UiSelectionItem::Text {
+                path,
+                text,
+                bind: table
+                    .get::<_, Option<String>>("bind")?
+                    .map(|bind| parse_state_path(&bind)),
+            },
+            Some("slider_int") => UiSelectionItem::SliderInt {
+                path,
+                text,
+                min: get_or(&table, "min", 0)?,
+                max: get_or(&table, "max", 100)?,
+            },
+            Some("slider_float") => UiSelectionItem::SliderFloat {
+                path,
+                text,
+                min: get_or(&table, "min", 0.0)?,
+                max: get_or(&table, "max", 1.0)?,
+            },
+            Some("drag_float") => UiSelectionItem::DragFloat {
+                path,
+                text,
+                speed: get_or(&table, "speed", 0.1)?,
+                min: get_or(&table, "min", f32::MIN)?,
+                max: get_or(&table, "max", f32::MAX)?,
+            },
+            Some("checkbox") => UiSelectionItem::Checkbox { path, text },
+            Some("input_text") => UiSelectionItem::InputText { path, text },
+            Some("combo") => UiSelectionItem::Combo {
+                path,
+                text,
+                options: get_options(&table)?,
+            },
+            Some("radio") => UiSelectionItem::Radio {
+                path,
+                text,
+                options: get_options(&table)?,
+            },
+            Some("color_edit") => UiSelectionItem::ColorEdit { path, text },
+            Some("collapsing_header") => UiSelectionItem::CollapsingHeader {
+                open: get_or(&table, "open", false)?,
+                items: build_content(&path, &table)?,
+                path,
+                text,
+            },
+            Some(other) => {
+                return Err(rlua::Error::RuntimeError(format!(
+                    "{}: unknown type {}",
+                    path, other
+                )))
+            }
+        };
+        Ok(item)
+    }
+
+    /// `None` for the items that have nothing to read from their table
+    fn path(&self) -> Option<&ItemPath> {
+        match self {
+            UiSelectionItem::Button { path, .. }
+            | UiSelectionItem::SliderInt { path, .. }
+            | UiSelectionItem::SliderFloat { path, .. }
+            | UiSelectionItem::DragFloat { path, .. }
+            | UiSelectionItem::Checkbox { path, .. }
+            | UiSelectionItem::InputText { path, .. }
+            | UiSelectionItem::Combo { path, .. }
+            | UiSelectionItem::Radio { path, .. }
+            | UiSelectionItem::ColorEdit { path, .. }
+            | UiSelectionItem::Text { path, .. }
+            | UiSelectionItem::Led { path, .. }
+            | UiSelectionItem::Canvas { path, .. }
+            | UiSelectionItem::CollapsingHeader { path, .. }
+            | UiSelectionItem::TabBar { path, .. }
+            | UiSelectionItem::Child { path, .. }
+            | UiSelectionItem::Columns { path, .. }
+            | UiSelectionItem::Indent { path, .. } => Some(path),
+            UiSelectionItem::SameLine | UiSelectionItem::Separator | UiSelectionItem::Spacing => {
+                None
+            }
+        }
+    }
+
+    /// items holding other items show their tooltip themselves, over their header,
+    /// tabs or the area they take
+    fn is_container(&self) -> bool {
+        matches!(
+            self,
+            UiSelectionItem::CollapsingHeader { .. }
+                | UiSelectionItem::TabBar { .. }
+                | UiSelectionItem::Child { .. }
+                | UiSelectionItem::Columns { .. }
+                | UiSelectionItem::Indent { .. }
+        )
+    }
+
+    /// a disabled item is drawn faded and its changes are dropped,
+    /// the items inside a disabled layout item are disabled too
+    fn render(&self, ui: &imgui::Ui, lua: &MpLua, enabled: bool) -> rlua::Result<()> {
+        let props = match self.path() {
+            Some(path) => lua.item_props(path)?,
+            None => ItemProps::default(),
+        };
+        if !props.visible {
+            return Ok(());
+        }
+        let enabled = enabled && props.enabled;
+        let id = props.id.as_ref().map(|key| ui.push_id(key.as_str()));
+        let style = if enabled {
+            None
+        } else {
+            Some(ui.push_style_var(StyleVar::Alpha(DISABLED_ALPHA)))
+        };
+        let result = self.render_widget(ui, lua, &props, enabled);
+        if !self.is_container() {
+            show_tooltip(ui, &props);
+        }
+        if let Some(style) = style {
+            style.pop(ui);
+        }
+        if let Some(id) = id {
+            id.pop(ui);
+        }
+        result
+    }
+
+    fn render_widget(
+        &self,
+        ui: &imgui::Ui,
+        lua: &MpLua,
+        props: &ItemProps,
+        enabled: bool,
+    ) -> rlua::Result<()> {
+        let label = props.text.as_deref();
+        match self {
+            UiSelectionItem::Button { path, text } => {
+                let text = label.unwrap_or(text);
+                if ui.button(&im_str!("{}###widget", text), [200f32, 30f32]) && enabled {
+                    lua.click_selection(path)?;
+                }
+            }
+            UiSelectionItem::SliderInt {
+                path,
+                text,
+                min,
+                max,
+            } => {
+                let text = label.unwrap_or(text);
+                let mut value = lua.selection_value::<f64>(path)?.map_or(*min, |v| v as i32);
+                if Slider::new(&im_str!("{}###widget", text), *min..=*max).build(ui, &mut value)
+                    && enabled
+                {
+                    lua.set_selection_value(path, value)?;
+                }
+            }
+            UiSelectionItem::SliderFloat {
+                path,
+                text,
+                min,
+                max,
+            } => {
+                let text = label.unwrap_or(text);
+                let mut value = lua.selection_value::<f32>(path)?.unwrap_or(*min);
+                if Slider::new(&im_str!("{}###widget", text), *min..=*max).build(ui, &mut value)
+                    && enabled
+                {
+                    lua.set_selection_value(path, value)?;
+                }
+            }
+            UiSelectionItem::DragFloat {
+                path,
+                text,
+                speed,
+                min,
+                max,
+            } => {
+                let text = label.unwrap_or(text);
+                let mut value = lua.selection_value::<f32>(path)?.unwrap_or(0.0);
+                if Drag::new(&im_str!("{}###widget", text))
+                    .range(*min..=*max)
+                    .speed(*speed)
+                    .build(ui, &mut value)
+                    && enabled
+                {
+                    lua.set_selection_value(path, value)?;
+                }
+            }
+            UiSelectionItem::Checkbox { path, text } => {
+                let text = label.unwrap_or(text);
+                let mut value = lua.selection_value::<bool>(path)?.unwrap_or(false);
+                if ui.checkbox(&im_str!("{}###widget", text), &mut value) && enabled {
+                    lua.set_selection_value(path, value)?;
+                }
+            }
+            UiSelectionItem::InputText { path, text } => {
+                let text = label.unwrap_or(text);
+                let value = lua.selection_value::<String>(path)?.unwrap_or_default();
+                let mut buf = ImString::with_capacity(INPUT_TEXT_CAPACITY);
+                buf.push_str(&value);
+                if ui
+                    .input_text(&im_str!("{}###widget", text), &mut buf)
+                    .build()
+                    && enabled
+                {
+                    lua.set_selection_value(path, buf.to_str())?;
+                }
+            }
+            UiSelectionItem::Combo {
+                path,
+                text,
+                options,
+            } => {
+                let text = label.unwrap_or(text);
+                let value = lua.selection_value::<String>(path)?;
+                let mut current = selected_option(options, value);
+                let labels = options.iter().collect::<Vec<&ImString>>();
+                if ComboBox::new(&im_str!("{}###widget", text)).build_simple_string(
+                    ui,
+                    &mut current,
+                    &labels,
+                ) && enabled
+                {
+                    lua.set_selection_value(path, options[current].to_str())?;
+                }
+            }
+            UiSelectionItem::Radio {
+                path,
+                text,
+                options,
+            } => {
+                let text = label.unwrap_or(text);
+                let value = lua.selection_value::<String>(path)?;
+                let mut current = selected_option(options, value);
+                ui.text(&im_str!("{}", text));
+                let mut changed = false;
+                for (i, option) in options.iter().enumerate() {
+                    if i > 0 {
+                        ui.same_line(0.0);
+                    }
+                    changed |= ui.radio_button(&im_str!("{}##{}", option, i), &mut current, i);
+                }
+                if changed && enabled {
+                    lua.set_selection_value(path, options[current].to_str())?;
+                }
+            }
+            UiSelectionItem::ColorEdit { path, text } => {
+                let text = label.unwrap_or(text);
+                let value = lua.selection_value::<Vec<f32>>(path)?.unwrap_or_default();
+                let mut color = [1.0f32; 4];
+                for (c, v) in color.iter_mut().zip(value) {
+                    *c = v;
+                }
+                if ColorEdit::new(&im_str!("{}###widget", text), &mut color).build(ui) && enabled {
+                    lua.set_selection_value(path, color.to_vec())?;
+                }
+            }
+            UiSelectionItem::Text { text, bind, .. } => {
+                let text = label.unwrap_or(text);
+                match bind {
+                    Some(bind) => ui.text(&im_str!("{}: {}", text, lua.display_state_path(bind)?)),
+                    None => ui.text(&im_str!("{}", text)),
+                }
+            }
+            UiSelectionItem::Led { path, input } => {
+                lua.render_led_item(ui, path, &mut input.borrow_mut(), enabled)?
+            }
+            UiSelectionItem::Canvas { path, size } => lua.render_canvas_item(ui, path, *size)?,
+            UiSelectionItem::SameLine => ui.same_line(0.0),
+            UiSelectionItem::Separator => ui.separator(),
+            UiSelectionItem::Spacing => ui.spacing(),
+            UiSelectionItem::CollapsingHeader {
+                text, open, items, ..
+            } => {
+                let text = label.unwrap_or(text);
+                let is_open = CollapsingHeader::new(&im_str!("{}###widget", text))
+                    .default_open(*open)
+                    .build(ui);
+                show_tooltip(ui, props);
+                if is_open {
+                    render_items(items, ui, lua, enabled);
+                }
+            }
+            UiSelectionItem::TabBar { tabs, .. } => render_tab_bar(ui, tabs, lua, props, enabled),
+            UiSelectionItem::Child {
+                size,
+                border,
+                items,
+                ..
+            } => {
+                let min = ui.cursor_screen_pos();
+                ChildWindow::new(im_str!("##child"))
+                    .size(*size)
+                    .border(*border)
+                    .build(ui, || render_items(items, ui, lua, enabled));
+                let child_size = ui.item_rect_size();
+                show_area_tooltip(
+                    ui,
+                    props,
+                    min,
+                    [min[0] + child_size[0], min[1] + child_size[1]],
+                );
+            }
+            UiSelectionItem::Columns {
+                count,
+                border,
+                items,
+                ..
+            } => {
+                let min = ui.cursor_screen_pos();
+                let width = ui.content_region_avail()[0];
+                ui.columns(*count, im_str!("##columns"), *border);
+                for item in items {
+                    lua.log_result(&item.render(ui, lua, enabled));
+                    ui.next_column();
+                }
+                ui.columns(1, im_str!(""), false);
+                let max = [min[0] + width, ui.cursor_screen_pos()[1]];
+                show_area_tooltip(ui, props, min, max);
+            }
+            UiSelectionItem::Indent { width, items, .. } => {
+                let min = ui.cursor_screen_pos();
+                let area_width = ui.content_region_avail()[0];
+                match width {
+                    Some(width) => ui.indent_by(*width),
+                    None => ui.indent(),
+                }
+                render_items(items, ui, lua, enabled);
+                match width {
+                    Some(width) => ui.unindent_by(*width),
+                    None => ui.unindent(),
+                }
+                let max = [min[0] + area_width, ui.cursor_screen_pos()[1]];
+                show_area_tooltip(ui, props, min, max);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// combo and radio values are the option text, unknown values select the first option
+fn selected_option(options: &[ImString], value: Option<String>) -> usize {
+    value
+        .and_then(|value| options.iter().position(|o| o.to_str() == value))
+        .unwrap_or(0)
+}
+
+/// the items of `mp_selection`, or the content of a window of `mp_windows`
+pub struct UiSelection {
+    window: Option<usize>,
+    items: Vec<UiSelectionItem>,
+}
+
+impl UiSelection {
+    pub fn new(window: Option<usize>) -> Self {
+        UiSelection {
+            window,
+            items: vec![],
+        }
+    }
+
+    pub fn build(&mut self, lua_table: Table) -> rlua::Result<()> {
+        self.items = build_items(&ItemPath::root(self.window), lua_table)?;
+        Ok(())
+    }
+
+    pub fn render(&self, ui: &imgui::Ui, lua: &MpLua) {
+        render_items(&self.items, ui, lua, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlua::Lua;
+
+    const SELECTION: &str =
+        r#"mp_selection = { { text = "a" }, { type = "group", content = { { text = "b" } } } }"#;
+
+    fn flag(item: &str) -> bool {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            let item = lua_ctx.load(item).eval::<Table>().unwrap();
+            dynamic_flag(&item, "enabled").unwrap()
+        })
+    }
+
+    fn item_text(script: &str, path: ItemPath) -> rlua::Result<String> {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            lua_ctx.load(script).exec()?;
+            path.table(lua_ctx)?.get("text")
+        })
+    }
+
+    #[test]
+    fn item_path_finds_selection_items() {
+        let script = SELECTION;
+        assert_eq!(item_text(script, ItemPath::selection(1)).unwrap(), "a");
+        let nested = ItemPath {
+            window: None,
+            indices: vec![2, 1],
+        };
+        assert_eq!(item_text(script, nested).unwrap(), "b");
+        assert!(item_text(script, ItemPath::selection(3)).is_err());
+    }
+
+    #[test]
+    fn item_path_finds_window_items() {
+        let script = r#"mp_windows = { { content = { { text = "w" } } } }"#;
+        let path = ItemPath {
+            window: Some(1),
+            indices: vec![1],
+        };
+        assert_eq!(path.to_string(), "mp_windows[1].content[1]");
+        assert_eq!(item_text(script, path).unwrap(), "w");
+    }
+
+    fn signature<'lua>(lua_ctx: rlua::Context<'lua>) -> Vec<Value<'lua>> {
+        let mut signature = vec![];
+        let items = lua_ctx.globals().get::<_, Table>("mp_selection").unwrap();
+        write_signature(&items, &mut signature).unwrap();
+        signature
+    }
+
+    /// whether `change` keeps the signature of `mp_selection`
+    fn keeps_signature(change: &str) -> bool {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            lua_ctx.load(SELECTION).exec().unwrap();
+            let built = lua_ctx.create_sequence_from(signature(lua_ctx)).unwrap();
+            lua_ctx.load(change).exec().unwrap();
+            same_signature(lua_ctx, &built, signature(lua_ctx)).unwrap()
+        })
+    }
+
+    #[test]
+    fn signature_ignores_edits_inside_items() {
+        assert!(keeps_signature(r#"mp_selection[1].text = "c""#));
+        assert!(keeps_signature(r#"mp_selection[2].content[1].text = "c""#));
+    }
+
+    #[test]
+    fn signature_changes_with_the_item_tables() {
+        assert!(!keeps_signature(r#"mp_selection[1] = { text = "a" }"#));
+        assert!(!keeps_signature(r#"mp_selection[3] = { text = "c" }"#));
+        assert!(!keeps_signature(
+            r#"mp_selection[2].content = { { text = "b" } }"#
+        ));
+        assert!(!keeps_signature(r#"mp_selection[1].type = "checkbox""#));
+        assert!(!keeps_signature(r#"table.remove(mp_selection, 1)"#));
+    }
+
+    #[test]
+    fn item_identity_follows_the_table() {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            let a = lua_ctx.create_table().unwrap();
+            let b = lua_ctx.create_table().unwrap();
+            let id = item_identity(lua_ctx, &a).unwrap();
+            assert_ne!(item_identity(lua_ctx, &b).unwrap(), id);
+            assert_eq!(item_identity(lua_ctx, &a).unwrap(), id);
+        });
+    }
+
+    #[test]
+    fn dynamic_flag_defaults_to_true_when_missing() {
+        assert!(flag("{}"));
+    }
+
+    #[test]
+    fn dynamic_flag_follows_lua_truthiness() {
+        assert!(flag("{ enabled = true }"));
+        assert!(flag("{ enabled = 0 }"));
+        assert!(!flag("{ enabled = false }"));
+        assert!(flag("{ enabled = function() return 1 end }"));
+        assert!(!flag("{ enabled = function() return false end }"));
+        assert!(!flag("{ enabled = function() end }"));
+    }
+}
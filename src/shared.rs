@@ -0,0 +1,40 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// state shared between `MpLua` and the `mp` functions. lua callbacks must be `Send`,
+/// hence the `Arc<Mutex<_>>`, clones share the same value
+pub struct Shared<T>(Arc<Mutex<T>>);
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Shared(Arc::new(Mutex::new(value)))
+    }
+
+    pub fn lock(&self) -> MutexGuard<T> {
+        self.0.lock().unwrap()
+    }
+}
+
+// derived impls would ask `T` for `Clone` too
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(self.0.clone())
+    }
+}
+
+impl<T: Default> Default for Shared<T> {
+    fn default() -> Self {
+        Shared::new(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_value() {
+        let shared = Shared::<Vec<u32>>::default();
+        shared.clone().lock().push(1);
+        assert_eq!(*shared.lock(), vec![1]);
+    }
+}
@@ -0,0 +1,282 @@
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, Rect};
+use ggez::{Context, GameResult};
+use imgui::WindowDrawList;
+use rlua::{Integer, Table, Value};
+
+const CIRCLE_TOLERANCE: f32 = 0.5;
+const DEFAULT_TEXT_SIZE: f32 = 18.0;
+
+#[derive(Debug, Copy, Clone)]
+enum ShowMode {
+    Fill,
+    Stroke(f32),
+}
+
+impl ShowMode {
+    fn draw_mode(self) -> DrawMode {
+        match self {
+            ShowMode::Fill => DrawMode::fill(),
+            ShowMode::Stroke(width) => DrawMode::stroke(width),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum ShowShape {
+    Rect {
+        pos: [f32; 2],
+        size: [f32; 2],
+    },
+    Circle {
+        pos: [f32; 2],
+        radius: f32,
+    },
+    Line {
+        points: Vec<[f32; 2]>,
+        width: f32,
+    },
+    Polygon {
+        points: Vec<[f32; 2]>,
+    },
+    Text {
+        pos: [f32; 2],
+        text: String,
+        size: f32,
+    },
+}
+
+#[derive(Debug)]
+struct ShowItem {
+    shape: ShowShape,
+    color: Color,
+    mode: ShowMode,
+    z: i64,
+}
+
+/// accepts `{ x = 1, y = 2 }` or `{ 1, 2 }`
+pub fn read_point(table: &Table) -> rlua::Result<[f32; 2]> {
+    if table.contains_key("x")? {
+        Ok([table.get("x")?, table.get("y")?])
+    } else {
+        Ok([table.get(1)?, table.get(2)?])
+    }
+}
+
+/// accepts `{ w = 1, h = 2 }` or `{ 1, 2 }`
+pub fn read_size(table: &Table) -> rlua::Result<[f32; 2]> {
+    if table.contains_key("w")? {
+        Ok([table.get("w")?, table.get("h")?])
+    } else {
+        Ok([table.get(1)?, table.get(2)?])
+    }
+}
+
+fn read_points(table: &Table) -> rlua::Result<Vec<[f32; 2]>> {
+    let mut points = vec![];
+    for point in table.clone().sequence_values::<Table>() {
+        points.push(read_point(&point?)?);
+    }
+    Ok(points)
+}
+
+/// accepts `{ r, g, b, a }` or `{ r = .., g = .., b = .., a = .. }` with components in 0..1,
+/// alpha defaults to 1
+pub fn read_color(table: &Table) -> rlua::Result<Color> {
+    if table.contains_key("r")? {
+        Ok(Color::new(
+            table.get("r")?,
+            table.get("g")?,
+            table.get("b")?,
+            table.get::<_, Option<f32>>("a")?.unwrap_or(1.0),
+        ))
+    } else {
+        Ok(Color::new(
+            table.get(1)?,
+            table.get(2)?,
+            table.get(3)?,
+            table.get::<_, Option<f32>>(4)?.unwrap_or(1.0),
+        ))
+    }
+}
+
+fn shape_error(shape_type: &str, message: &str) -> rlua::Error {
+    rlua::Error::RuntimeError(format!("mp_show {}: {}", shape_type, message))
+}
+
+impl ShowItem {
+    fn build(table: Table) -> rlua::Result<ShowItem> {
+        let shape_type = table.get::<_, String>("type")?;
+        let width = table.get::<_, Option<f32>>("width")?.unwrap_or(1.0);
+        let shape = match shape_type.as_str() {
+            "rect" => ShowShape::Rect {
+                pos: read_point(&table.get("pos")?)?,
+                size: read_size(&table.get("size")?)?,
+            },
+            "circle" => ShowShape::Circle {
+                pos: read_point(&table.get("pos")?)?,
+                radius: table.get("radius")?,
+            },
+            "line" => {
+                let points = read_points(&table.get("points")?)?;
+                if points.len() < 2 {
+                    return Err(shape_error(&shape_type, "needs at least 2 points"));
+                }
+                ShowShape::Line { points, width }
+            }
+            "polygon" => {
+                let points = read_points(&table.get("points")?)?;
+                if points.len() < 3 {
+                    return Err(shape_error(&shape_type, "needs at least 3 points"));
+                }
+                ShowShape::Polygon { points }
+            }
+            "text" => ShowShape::Text {
+                pos: read_point(&table.get("pos")?)?,
+                text: table.get("text")?,
+                size: table
+                    .get::<_, Option<f32>>("size")?
+                    .unwrap_or(DEFAULT_TEXT_SIZE),
+            },
+            _ => return Err(shape_error(&shape_type, "unknown type")),
+        };
+        let color = match table.get::<_, Option<Table>>("color")? {
+            Some(color) => read_color(&color)?,
+            None => graphics::WHITE,
+        };
+        let mode = match table.get::<_, Option<String>>("mode")?.as_deref() {
+            None | Some("fill") => ShowMode::Fill,
+            Some("stroke") => ShowMode::Stroke(width),
+            Some(other) => {
+                return Err(shape_error(&shape_type, &format!("unknown mode {}", other)))
+            }
+        };
+        let z = table.get::<_, Option<Integer>>("z")?.unwrap_or(0);
+        Ok(ShowItem {
+            shape,
+            color,
+            mode,
+            z,
+        })
+    }
+
+    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let draw_mode = self.mode.draw_mode();
+        match &self.shape {
+            ShowShape::Rect { pos, size } => {
+                let rect = Rect::new(pos[0], pos[1], size[0], size[1]);
+                let mesh = Mesh::new_rectangle(ctx, draw_mode, rect, self.color)?;
+                graphics::draw(ctx, &mesh, DrawParam::default())
+            }
+            ShowShape::Circle { pos, radius } => {
+                let mesh =
+                    Mesh::new_circle(ctx, draw_mode, *pos, *radius, CIRCLE_TOLERANCE, self.color)?;
+                graphics::draw(ctx, &mesh, DrawParam::default())
+            }
+            ShowShape::Line { points, width } => {
+                let mesh = Mesh::new_line(ctx, points.as_slice(), *width, self.color)?;
+                graphics::draw(ctx, &mesh, DrawParam::default())
+            }
+            ShowShape::Polygon { points } => {
+                let mesh = Mesh::new_polygon(ctx, draw_mode, points.as_slice(), self.color)?;
+                graphics::draw(ctx, &mesh, DrawParam::default())
+            }
+            ShowShape::Text { pos, text, size } => {
+                let text = graphics::Text::new((text.as_str(), graphics::Font::default(), *size));
+                graphics::draw(
+                    ctx,
+                    &text,
+                    DrawParam::default().dest(*pos).color(self.color),
+                )
+            }
+        }
+    }
+
+    /// the same shapes in an imgui window, positions are relative to `origin`.
+    /// polygons are filled as a fan, so only convex ones fill correctly,
+    /// and text uses the imgui font size
+    fn draw_ui(&self, draw_list: &WindowDrawList, origin: [f32; 2]) {
+        let color: [f32; 4] = self.color.into();
+        let at = |point: &[f32; 2]| [origin[0] + point[0], origin[1] + point[1]];
+        let (filled, thickness) = match self.mode {
+            ShowMode::Fill => (true, 1.0),
+            ShowMode::Stroke(width) => (false, width),
+        };
+        match &self.shape {
+            ShowShape::Rect { pos, size } => draw_list
+                .add_rect(at(pos), at(&[pos[0] + size[0], pos[1] + size[1]]), color)
+                .filled(filled)
+                .thickness(thickness)
+                .build(),
+            ShowShape::Circle { pos, radius } => draw_list
+                .add_circle(at(pos), *radius, color)
+                .filled(filled)
+                .thickness(thickness)
+                .build(),
+            ShowShape::Line { points, width } => {
+                for pair in points.windows(2) {
+                    draw_list
+                        .add_line(at(&pair[0]), at(&pair[1]), color)
+                        .thickness(*width)
+                        .build();
+                }
+            }
+            ShowShape::Polygon { points } if filled => {
+                for pair in points[1..].windows(2) {
+                    draw_list
+                        .add_triangle(at(&points[0]), at(&pair[0]), at(&pair[1]), color)
+                        .filled(true)
+                        .build();
+                }
+            }
+            ShowShape::Polygon { points } => {
+                for (i, point) in points.iter().enumerate() {
+                    let next = &points[(i + 1) % points.len()];
+                    draw_list
+                        .add_line(at(point), at(next), color)
+                        .thickness(thickness)
+                        .build();
+                }
+            }
+            ShowShape::Text { pos, text, .. } => draw_list.add_text(at(pos), color, text),
+        }
+    }
+}
+
+/// the drawing area behind the imgui windows, built from `mp_show`.
+/// canvas items of the selection draw the same shapes inside a window
+#[derive(Default)]
+pub struct Show {
+    items: Vec<ShowItem>,
+}
+
+impl Show {
+    pub fn build(&mut self, lua_table: Table) -> rlua::Result<()> {
+        for value in lua_table.sequence_values::<Value>() {
+            if let Value::Table(item) = value? {
+                self.items.push(ShowItem::build(item)?);
+            }
+        }
+        // stable sort, so items with the same z keep the table order
+        self.items.sort_by_key(|item| item.z);
+        Ok(())
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        for item in &self.items {
+            item.draw(ctx)?;
+        }
+        Ok(())
+    }
+
+    /// draw at the cursor of an imgui window and take `size` of its space
+    pub fn draw_ui(&self, ui: &imgui::Ui, size: [f32; 2]) {
+        let origin = ui.cursor_screen_pos();
+        {
+            let draw_list = ui.get_window_draw_list();
+            for item in &self.items {
+                item.draw_ui(&draw_list, origin);
+            }
+        }
+        ui.dummy(size);
+    }
+}
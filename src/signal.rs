@@ -1,2 +0,0 @@
-pub const SIGNAL_TABLE: &str = "__mp_signals";
-pub const SIGNAL_RELOAD_SELECTION: i64 = 1i64;
@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use rlua::{Integer, Table, ToLua, Value};
+
+use crate::dump::{dump_value, DumpFormat};
+use crate::state::{format_state_path, StateKey, STATE_TABLE};
+
+const SNAPSHOT_DIR: &str = "snapshots";
+const SNAPSHOT_EXTENSION: &str = "lua";
+
+/// a copy of `mp_state` that lives outside of lua
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateValue {
+    Boolean(bool),
+    Integer(Integer),
+    Number(f64),
+    Str(String),
+    Table(Vec<(StateKey, StateValue)>),
+}
+
+impl StateValue {
+    /// copy a lua value, the paths of values that can't be copied are added to `skipped`.
+    /// a table inside itself, e.g. `mp_state.a = mp_state`, is skipped as a cycle
+    pub fn capture<'lua>(
+        lua_ctx: rlua::Context<'lua>,
+        value: Value<'lua>,
+        skipped: &mut Vec<String>,
+    ) -> rlua::Result<Option<StateValue>> {
+        // keyed by the tables from the root to the current one, lua compares
+        // table keys by identity
+        let ancestors = lua_ctx.create_table()?;
+        capture_value(value, &mut vec![], &ancestors, skipped)
+    }
+}
+
+fn capture_value<'lua>(
+    value: Value<'lua>,
+    path: &mut Vec<StateKey>,
+    ancestors: &Table<'lua>,
+    skipped: &mut Vec<String>,
+) -> rlua::Result<Option<StateValue>> {
+    let captured = match value {
+        Value::Nil => None,
+        Value::Boolean(b) => Some(StateValue::Boolean(b)),
+        Value::Integer(i) => Some(StateValue::Integer(i)),
+        Value::Number(n) => Some(StateValue::Number(n)),
+        Value::String(s) => Some(StateValue::Str(String::from(s.to_str()?))),
+        Value::Table(table) if ancestors.raw_get::<_, bool>(table.clone())? => {
+            skipped.push(format!("{} (cycle)", display_path(path)));
+            None
+        }
+        Value::Table(table) => {
+            ancestors.raw_set(table.clone(), true)?;
+            let mut fields = vec![];
+            for pair in table.pairs::<Value, Value>() {
+                let (key, value) = pair?;
+                let key = match StateKey::from_value(&key) {
+                    Some(key) => key,
+                    None => {
+                        skipped.push(format!("{}[{}] (key)", display_path(path), type_name(&key)));
+                        continue;
+                    }
+                };
+                path.push(key.clone());
+                let captured = capture_value(value, path, ancestors, skipped)?;
+                path.pop();
+                if let Some(captured) = captured {
+                    fields.push((key, captured));
+                }
+            }
+            ancestors.raw_set(table, Value::Nil)?;
+            Some(StateValue::Table(fields))
+        }
+        other => {
+            skipped.push(format!("{} ({})", display_path(path), type_name(&other)));
+            None
+        }
+    };
+    Ok(captured)
+}
+
+impl<'lua> ToLua<'lua> for &StateValue {
+    fn to_lua(self, lua_ctx: rlua::Context<'lua>) -> rlua::Result<Value<'lua>> {
+        match self {
+            StateValue::Boolean(b) => Ok(Value::Boolean(*b)),
+            StateValue::Integer(i) => Ok(Value::Integer(*i)),
+            StateValue::Number(n) => Ok(Value::Number(*n)),
+            StateValue::Str(s) => Ok(Value::String(lua_ctx.create_string(s)?)),
+            StateValue::Table(fields) => {
+                let table = lua_ctx.create_table()?;
+                for (key, value) in fields {
+                    table.set(key, value)?;
+                }
+                Ok(Value::Table(table))
+            }
+        }
+    }
+}
+
+fn display_path(path: &[StateKey]) -> String {
+    if path.is_empty() {
+        String::from(STATE_TABLE)
+    } else {
+        format!("{}.{}", STATE_TABLE, format_state_path(path))
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Nil => "nil",
+        Value::Boolean(_) => "boolean",
+        Value::Integer(_) | Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Table(_) => "table",
+        Value::Function(_) => "function",
+        Value::Thread(_) => "thread",
+        Value::UserData(_) | Value::LightUserData(_) => "userdata",
+        Value::Error(_) => "error",
+    }
+}
+
+/// copy `mp_state`, and the paths of the values that were left out
+pub fn capture_state(lua_ctx: rlua::Context) -> rlua::Result<(StateValue, Vec<String>)> {
+    let state = lua_ctx.globals().get::<_, Value>(STATE_TABLE)?;
+    let mut skipped = vec![];
+    let captured = StateValue::capture(lua_ctx, state, &mut skipped)?;
+    Ok((captured.unwrap_or(StateValue::Table(vec![])), skipped))
+}
+
+/// refill `mp_state` in place, so scripts holding on to the table see the change
+pub fn restore_state(lua_ctx: rlua::Context, state: &StateValue) -> rlua::Result<()> {
+    let fields = match state {
+        StateValue::Table(fields) => fields,
+        _ => {
+            return Err(rlua::Error::RuntimeError(String::from(
+                "a snapshot must be a table",
+            )))
+        }
+    };
+    let globals = lua_ctx.globals();
+    let table = match globals.get::<_, Option<Table>>(STATE_TABLE)? {
+        Some(table) => table,
+        None => {
+            let table = lua_ctx.create_table()?;
+            globals.set(STATE_TABLE, table.clone())?;
+            table
+        }
+    };
+    let keys = table
+        .clone()
+        .pairs::<Value, Value>()
+        .map(|pair| pair.map(|(key, _)| key))
+        .collect::<rlua::Result<Vec<_>>>()?;
+    for key in keys {
+        table.set(key, Value::Nil)?;
+    }
+    for (key, value) in fields {
+        table.set(key, value)?;
+    }
+    Ok(())
+}
+
+/// the console message for values `save` left out
+pub fn skipped_message(name: &str, skipped: &[String]) -> Option<String> {
+    if skipped.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "snapshot '{}' left out values that can't be saved: {}",
+        name,
+        skipped.join(", ")
+    ))
+}
+
+fn check_name(name: &str) -> rlua::Result<()> {
+    let is_valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_valid {
+        Ok(())
+    } else {
+        Err(rlua::Error::RuntimeError(format!(
+            "bad snapshot name '{}', use letters, digits, '_' and '-'",
+            name
+        )))
+    }
+}
+
+/// named copies of `mp_state`, kept in memory and in `snapshots/<name>.lua`
+/// next to the entry file
+pub struct Snapshots {
+    slots: BTreeMap<String, StateValue>,
+    dir: PathBuf,
+    /// the listing of `dir` and `slots`, cleared by `save` and `delete`
+    names: Option<Vec<String>>,
+}
+
+impl Snapshots {
+    pub fn new(project_dir: PathBuf) -> Self {
+        Snapshots {
+            slots: BTreeMap::new(),
+            dir: project_dir.join(SNAPSHOT_DIR),
+            names: None,
+        }
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, SNAPSHOT_EXTENSION))
+    }
+
+    /// the slots in memory and on disk, sorted. the directory is only read again
+    /// after a save or a delete
+    pub fn names(&mut self) -> Vec<String> {
+        if self.names.is_none() {
+            self.names = Some(self.list());
+        }
+        self.names.clone().unwrap_or_default()
+    }
+
+    fn list(&self) -> Vec<String> {
+        let mut names = self.slots.keys().cloned().collect::<Vec<_>>();
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some(SNAPSHOT_EXTENSION) {
+                    continue;
+                }
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(String::from(stem));
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// returns the values that were left out
+    pub fn save(&mut self, lua_ctx: rlua::Context, name: &str) -> rlua::Result<Vec<String>> {
+        check_name(name)?;
+        let (state, skipped) = capture_state(lua_ctx)?;
+        let text = dump_value(state.to_lua(lua_ctx)?, DumpFormat::Lua, 0)?;
+        fs::create_dir_all(&self.dir).map_err(rlua::Error::external)?;
+        fs::write(self.file(name), text).map_err(rlua::Error::external)?;
+        self.slots.insert(String::from(name), state);
+        self.names = None;
+        Ok(skipped)
+    }
+
+    pub fn load(&mut self, lua_ctx: rlua::Context, name: &str) -> rlua::Result<()> {
+        check_name(name)?;
+        if !self.slots.contains_key(name) {
+            let file = self.file(name);
+            let text = fs::read_to_string(&file)
+                .map_err(|e| rlua::Error::RuntimeError(format!("no snapshot '{}': {}", name, e)))?;
+            let value = lua_ctx
+                .load(&format!("return {}", text))
+                .set_name(&format!("@{}", file.display()))?
+                .eval::<Value>()?;
+            let state = StateValue::capture(lua_ctx, value, &mut vec![])?.ok_or_else(|| {
+                rlua::Error::RuntimeError(format!("snapshot '{}' is empty", name))
+            })?;
+            self.slots.insert(String::from(name), state);
+        }
+        restore_state(lua_ctx, &self.slots[name])
+    }
+
+    pub fn delete(&mut self, name: &str) -> rlua::Result<()> {
+        check_name(name)?;
+        self.slots.remove(name);
+        self.names = None;
+        let file = self.file(name);
+        if file.exists() {
+            fs::remove_file(file).map_err(rlua::Error::external)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlua::Lua;
+
+    fn capture(lua_ctx: rlua::Context, script: &str) -> (StateValue, Vec<String>) {
+        lua_ctx.load(script).exec().unwrap();
+        let (state, mut skipped) = capture_state(lua_ctx).unwrap();
+        skipped.sort();
+        (state, skipped)
+    }
+
+    #[test]
+    fn restore_brings_back_the_captured_state() {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            let (state, skipped) = capture(
+                lua_ctx,
+                r#"mp_state = { hp = 3, name = "a", pos = { x = 1.5 }, list = { 1, 2, 3 }, on = true }"#,
+            );
+            assert!(skipped.is_empty());
+            lua_ctx
+                .load("held = mp_state; mp_state.hp = 0; mp_state.extra = 1; mp_state.pos = nil")
+                .exec()
+                .unwrap();
+            restore_state(lua_ctx, &state).unwrap();
+            let restored = lua_ctx
+                .load(
+                    r#"return held == mp_state and mp_state.hp == 3 and mp_state.name == "a"
+                        and mp_state.pos.x == 1.5 and #mp_state.list == 3 and mp_state.on
+                        and mp_state.extra == nil"#,
+                )
+                .eval::<bool>()
+                .unwrap();
+            assert!(restored);
+        });
+    }
+
+    #[test]
+    fn cycles_are_left_out() {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            let (state, skipped) = capture(
+                lua_ctx,
+                "mp_state = { hp = 1, shared = {} }
+                 mp_state.a = mp_state
+                 mp_state.b = mp_state
+                 mp_state.shared.up = mp_state
+                 mp_state.again = mp_state.shared",
+            );
+            assert_eq!(
+                skipped,
+                vec![
+                    "mp_state.a (cycle)",
+                    "mp_state.again.up (cycle)",
+                    "mp_state.b (cycle)",
+                    "mp_state.shared.up (cycle)",
+                ]
+            );
+            // a table reached twice without a cycle is kept both times
+            let fields = match state {
+                StateValue::Table(fields) => fields,
+                other => panic!("not a table: {:?}", other),
+            };
+            let mut keys = fields
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .collect::<Vec<_>>();
+            keys.sort();
+            assert_eq!(keys, vec!["again", "hp", "shared"]);
+        });
+    }
+
+    #[test]
+    fn values_that_cant_be_copied_are_left_out() {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            let (_, skipped) = capture(
+                lua_ctx,
+                "mp_state = { f = function() end, [{}] = 1, n = 2 }",
+            );
+            assert_eq!(
+                skipped,
+                vec!["mp_state.f (function)", "mp_state[table] (key)"]
+            );
+        });
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+
+use rlua::{FromLua, Integer, Table, ToLua, Value};
+
+pub const STATE_TABLE: &str = "mp_state";
+
+/// a key of `mp_state` that can be kept outside of a lua context
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateKey {
+    Integer(Integer),
+    String(String),
+}
+
+impl StateKey {
+    /// only integer and string keys are addressable
+    pub fn from_value(value: &Value) -> Option<StateKey> {
+        match value {
+            Value::Integer(i) => Some(StateKey::Integer(*i)),
+            Value::Number(n) if n.fract() == 0.0 => Some(StateKey::Integer(*n as Integer)),
+            Value::String(s) => s.to_str().ok().map(|s| StateKey::String(String::from(s))),
+            _ => None,
+        }
+    }
+}
+
+impl<'lua> ToLua<'lua> for &StateKey {
+    fn to_lua(self, lua_ctx: rlua::Context<'lua>) -> rlua::Result<Value<'lua>> {
+        match self {
+            StateKey::Integer(i) => Ok(Value::Integer(*i)),
+            StateKey::String(s) => Ok(Value::String(lua_ctx.create_string(s)?)),
+        }
+    }
+}
+
+impl fmt::Display for StateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateKey::Integer(i) => write!(f, "{}", i),
+            StateKey::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// `"player.hp"` -> `[player, hp]`, numeric segments index arrays
+pub fn parse_state_path(path: &str) -> Vec<StateKey> {
+    path.split('.')
+        .map(|key| match key.parse::<Integer>() {
+            Ok(i) => StateKey::Integer(i),
+            Err(_) => StateKey::String(String::from(key)),
+        })
+        .collect()
+}
+
+pub fn format_state_path(keys: &[StateKey]) -> String {
+    keys.iter()
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn state_path_parent<'lua, 'k>(
+    lua_ctx: rlua::Context<'lua>,
+    keys: &'k [StateKey],
+) -> rlua::Result<(Table<'lua>, &'k StateKey)> {
+    let (last, parents) = keys
+        .split_last()
+        .ok_or_else(|| rlua::Error::RuntimeError(String::from("empty state path")))?;
+    let mut table = lua_ctx.globals().get::<_, Table>(STATE_TABLE)?;
+    for key in parents {
+        table = table.get(key)?;
+    }
+    Ok((table, last))
+}
+
+pub fn get_state_path<'lua, T: FromLua<'lua>>(
+    lua_ctx: rlua::Context<'lua>,
+    keys: &[StateKey],
+) -> rlua::Result<T> {
+    let (table, key) = state_path_parent(lua_ctx, keys)?;
+    table.get(key)
+}
+
+pub fn set_state_path<'lua, T: ToLua<'lua>>(
+    lua_ctx: rlua::Context<'lua>,
+    keys: &[StateKey],
+    value: T,
+) -> rlua::Result<()> {
+    let (table, key) = state_path_parent(lua_ctx, keys)?;
+    table.set(key, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlua::Lua;
+
+    #[test]
+    fn parse_state_path_splits_on_dots() {
+        assert_eq!(
+            parse_state_path("player.items.2"),
+            vec![
+                StateKey::String(String::from("player")),
+                StateKey::String(String::from("items")),
+                StateKey::Integer(2),
+            ]
+        );
+        assert_eq!(
+            parse_state_path("hp"),
+            vec![StateKey::String(String::from("hp"))]
+        );
+    }
+
+    #[test]
+    fn format_state_path_reverses_parse() {
+        let path = "player.items.2";
+        assert_eq!(format_state_path(&parse_state_path(path)), path);
+    }
+
+    #[test]
+    fn get_and_set_follow_the_path() {
+        let lua = Lua::new();
+        lua.context(|lua_ctx| {
+            lua_ctx
+                .load("mp_state = { player = { items = { 10, 20 } } }")
+                .exec()
+                .unwrap();
+            let path = parse_state_path("player.items.2");
+            assert_eq!(get_state_path::<Integer>(lua_ctx, &path).unwrap(), 20);
+            set_state_path(lua_ctx, &path, 30).unwrap();
+            assert_eq!(get_state_path::<Integer>(lua_ctx, &path).unwrap(), 30);
+            assert!(get_state_path::<Value>(lua_ctx, &[]).is_err());
+        });
+    }
+}
@@ -0,0 +1,292 @@
+use imgui::{im_str, Drag, ImString, TreeNode};
+use rlua::{Table, Value};
+
+use crate::lua::MpLua;
+use crate::state::{format_state_path, StateKey};
+
+/// bytes an imgui text input may grow to while editing a state string
+pub const INPUT_TEXT_CAPACITY: usize = 256;
+
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::Table(_) => String::from("table"),
+        Value::String(str) => String::from(str.to_str().unwrap_or("")),
+        Value::Boolean(b) => format!("{}", b),
+        Value::Nil => String::from("Nil"),
+        Value::Number(n) => format!("{}", n),
+        Value::Integer(n) => format!("{}", n),
+        _ => format!("{:?}", value),
+    }
+}
+
+/// like `display_value`, but tables are written out up to `depth` levels deep
+/// and strings are quoted, used by the repl
+pub fn pretty_value(value: &Value, depth: usize) -> String {
+    pretty_value_indented(value, depth, 0)
+}
+
+fn pretty_value_indented(value: &Value, depth: usize, indent: usize) -> String {
+    match value {
+        Value::Table(table) if depth > 0 => {
+            let mut fields = table
+                .clone()
+                .pairs::<Value, Value>()
+                .flatten()
+                .collect::<Vec<_>>();
+            if fields.is_empty() {
+                return String::from("{}");
+            }
+            // integer keys first and in order, so sequences read naturally
+            fields.sort_by_key(|(key, _)| match key {
+                Value::Integer(i) => (false, *i, String::new()),
+                _ => (true, 0, display_value(key)),
+            });
+            let padding = "  ".repeat(indent + 1);
+            let lines = fields
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "{}{} = {}",
+                        padding,
+                        display_value(key),
+                        pretty_value_indented(value, depth - 1, indent + 1)
+                    )
+                })
+                .collect::<Vec<_>>();
+            format!("{{\n{}\n{}}}", lines.join(",\n"), "  ".repeat(indent))
+        }
+        Value::String(_) => format!("{:?}", display_value(value)),
+        _ => display_value(value),
+    }
+}
+
+#[derive(Debug)]
+enum UiStatusItem {
+    Table {
+        key: String,
+        /// built from the keys above, a key a state path can't hold uses its position instead
+        id: String,
+        children: Vec<UiStatusItem>,
+    },
+    Integer {
+        key: String,
+        path: Vec<StateKey>,
+        value: i64,
+    },
+    Number {
+        key: String,
+        path: Vec<StateKey>,
+        value: f64,
+    },
+    Boolean {
+        key: String,
+        path: Vec<StateKey>,
+        value: bool,
+    },
+    Str {
+        key: String,
+        path: Vec<StateKey>,
+        value: String,
+    },
+    /// values that can't be edited, e.g. functions or entries with table keys
+    Text(ImString),
+}
+
+impl UiStatusItem {
+    fn render(&self, ui: &imgui::Ui, lua: &MpLua) -> rlua::Result<()> {
+        match self {
+            UiStatusItem::Table { key, id, children } => {
+                TreeNode::new(&im_str!("{}##{}", key, id)).build(ui, || {
+                    for child in children {
+                        lua.log_result(&child.render(ui, lua));
+                    }
+                });
+            }
+            UiStatusItem::Integer { key, path, value } => {
+                let mut value = *value;
+                if Drag::new(&im_str!("{}##{}", key, format_state_path(path))).build(ui, &mut value)
+                {
+                    lua.set_state_value(path, value)?;
+                }
+            }
+            UiStatusItem::Number { key, path, value } => {
+                let mut value = *value;
+                if Drag::new(&im_str!("{}##{}", key, format_state_path(path)))
+                    .speed(0.1)
+                    .build(ui, &mut value)
+                {
+                    lua.set_state_value(path, value)?;
+                }
+            }
+            UiStatusItem::Boolean { key, path, value } => {
+                let mut value = *value;
+                if ui.checkbox(&im_str!("{}##{}", key, format_state_path(path)), &mut value) {
+                    lua.set_state_value(path, value)?;
+                }
+            }
+            UiStatusItem::Str { key, path, value } => {
+                let mut buf = ImString::with_capacity(INPUT_TEXT_CAPACITY);
+                buf.push_str(value);
+                if ui
+                    .input_text(&im_str!("{}##{}", key, format_state_path(path)), &mut buf)
+                    .build()
+                {
+                    lua.set_state_value(path, buf.to_str())?;
+                }
+            }
+            UiStatusItem::Text(text) => ui.text(text),
+        }
+        Ok(())
+    }
+}
+
+/// the items of `lua_table` matching the lower case `filter`, read-only below unaddressable keys
+fn build_items<'lua>(
+    lua_table: Table<'lua>,
+    ancestors: &Table<'lua>,
+    path: &[StateKey],
+    id: &str,
+    editable: bool,
+    filter: &str,
+) -> rlua::Result<Vec<UiStatusItem>> {
+    // a table that is its own ancestor is shown as a cycle instead of being expanded again
+    ancestors.raw_set(lua_table.clone(), true)?;
+    let mut items = vec![];
+    for (index, pair) in lua_table.pairs::<Value, Value>().enumerate() {
+        let (key, value) = pair?;
+        let key_text = display_value(&key);
+        let matched = filter.is_empty() || key_text.to_lowercase().contains(filter);
+        let state_key = StateKey::from_value(&key);
+        let editable = editable && state_key.is_some();
+        let mut item_path = path.to_vec();
+        let item_id = match state_key {
+            Some(state_key) => {
+                let item_id = format!("{}/{:?}", id, state_key);
+                item_path.push(state_key);
+                item_id
+            }
+            // e.g. `1.5` next to `"1.5"`, or a table key
+            None => format!("{}/#{}", id, index),
+        };
+
+        let item = match value {
+            Value::Table(inner_table) if ancestors.raw_get::<_, bool>(inner_table.clone())? => {
+                if !matched {
+                    continue;
+                }
+                UiStatusItem::Text(im_str!("{}: <cycle>", key_text))
+            }
+            Value::Table(inner_table) => {
+                let inner_filter = if matched { "" } else { filter };
+                let children = build_items(
+                    inner_table,
+                    ancestors,
+                    &item_path,
+                    &item_id,
+                    editable,
+                    inner_filter,
+                )?;
+                if !matched && children.is_empty() {
+                    continue;
+                }
+                UiStatusItem::Table {
+                    key: key_text,
+                    id: item_id,
+                    children,
+                }
+            }
+            _ if !matched => continue,
+            Value::Integer(value) if editable => UiStatusItem::Integer {
+                key: key_text,
+                path: item_path,
+                value,
+            },
+            Value::Number(value) if editable => UiStatusItem::Number {
+                key: key_text,
+                path: item_path,
+                value,
+            },
+            Value::Boolean(value) if editable => UiStatusItem::Boolean {
+                key: key_text,
+                path: item_path,
+                value,
+            },
+            Value::String(value) if editable => UiStatusItem::Str {
+                key: key_text,
+                path: item_path,
+                value: String::from(value.to_str().unwrap_or("")),
+            },
+            value => UiStatusItem::Text(im_str!("{}: {}", key_text, display_value(&value))),
+        };
+        items.push(item);
+    }
+    ancestors.raw_set(lua_table, Value::Nil)?;
+    Ok(items)
+}
+
+pub struct UiStatus {
+    items: Vec<UiStatusItem>,
+}
+
+impl UiStatus {
+    pub fn new() -> Self {
+        UiStatus { items: vec![] }
+    }
+
+    pub fn build<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        lua_table: Table<'lua>,
+        filter: &str,
+    ) -> rlua::Result<()> {
+        let ancestors = lua_ctx.create_table()?;
+        self.items = build_items(lua_table, &ancestors, &[], "", true, &filter.to_lowercase())?;
+        Ok(())
+    }
+
+    pub fn render(&self, ui: &imgui::Ui, lua: &MpLua) {
+        for item in &self.items {
+            lua.log_result(&item.render(ui, lua));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlua::Lua;
+
+    fn build(source: &str) -> Vec<UiStatusItem> {
+        Lua::new().context(|lua_ctx| {
+            let table = lua_ctx.load(source).eval::<Table>().unwrap();
+            let mut status = UiStatus::new();
+            status.build(lua_ctx, table, "").unwrap();
+            status.items
+        })
+    }
+
+    #[test]
+    fn float_and_string_keys_get_their_own_ids() {
+        let items = build("{ [1.5] = { x = 1 }, [\"1.5\"] = { x = 2 } }");
+        let ids = items
+            .iter()
+            .map(|item| match item {
+                UiStatusItem::Table { id, .. } => id.clone(),
+                other => panic!("expected a table, got {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn values_below_unaddressable_keys_are_read_only() {
+        let items = build("{ [1.5] = { x = 1 } }");
+        match &items[0] {
+            UiStatusItem::Table { children, .. } => {
+                assert!(matches!(children[0], UiStatusItem::Text(_)))
+            }
+            other => panic!("expected a table, got {:?}", other),
+        }
+    }
+}
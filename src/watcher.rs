@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+pub struct FileWatcher {
+    files: HashMap<PathBuf, Option<SystemTime>>,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        FileWatcher {
+            files: HashMap::new(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    pub fn watch(&mut self, path: PathBuf) {
+        let modified = modified_time(&path);
+        self.files.insert(path, modified);
+    }
+
+    /// returns true when any watched file changed since the last poll.
+    /// polling is throttled so it is cheap to call every frame
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = false;
+        for (path, last_modified) in self.files.iter_mut() {
+            let modified = modified_time(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
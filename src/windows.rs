@@ -0,0 +1,109 @@
+use imgui::{im_str, Condition, ImString, Window};
+use rlua::Table;
+
+use crate::lua::MpLua;
+use crate::selection::UiSelection;
+use crate::show::{read_point, read_size};
+
+const DEFAULT_SIZE: [f32; 2] = [300.0, 300.0];
+const FIRST_POSITION: [f32; 2] = [80.0, 80.0];
+/// windows without a position are cascaded from `FIRST_POSITION`
+const CASCADE_STEP: f32 = 30.0;
+
+/// a window declared in `mp_windows`
+pub struct UiWindow {
+    /// the title plus a hidden id, so two windows can have the same title
+    title: ImString,
+    size: [f32; 2],
+    position: [f32; 2],
+    no_resize: bool,
+    auto_resize: bool,
+    collapsed: bool,
+    content: UiSelection,
+}
+
+impl UiWindow {
+    /// `{ title, size, position, flags = { "no_resize", "auto_resize", "collapsed" }, content }`,
+    /// `content` holds the same items as `mp_selection`
+    fn build(index: usize, table: Table) -> rlua::Result<UiWindow> {
+        let title = table
+            .get::<_, Option<String>>("title")?
+            .unwrap_or_else(|| format!("window {}", index));
+        let offset = CASCADE_STEP * (index - 1) as f32;
+        let mut window = UiWindow {
+            title: im_str!("{}##mp_windows{}", title, index),
+            size: match table.get::<_, Option<Table>>("size")? {
+                Some(size) => read_size(&size)?,
+                None => DEFAULT_SIZE,
+            },
+            position: match table.get::<_, Option<Table>>("position")? {
+                Some(position) => read_point(&position)?,
+                None => [FIRST_POSITION[0] + offset, FIRST_POSITION[1] + offset],
+            },
+            no_resize: false,
+            auto_resize: false,
+            collapsed: false,
+            content: UiSelection::new(Some(index)),
+        };
+        if let Some(flags) = table.get::<_, Option<Vec<String>>>("flags")? {
+            for flag in flags {
+                match flag.as_str() {
+                    "no_resize" => window.no_resize = true,
+                    "auto_resize" => window.auto_resize = true,
+                    "collapsed" => window.collapsed = true,
+                    other => {
+                        return Err(rlua::Error::RuntimeError(format!("unknown flag {}", other)))
+                    }
+                }
+            }
+        }
+        if let Some(content) = table.get::<_, Option<Table>>("content")? {
+            window.content.build(content)?;
+        }
+        Ok(window)
+    }
+
+    fn render(&self, ui: &imgui::Ui, lua: &MpLua) {
+        Window::new(&self.title)
+            .size(self.size, Condition::FirstUseEver)
+            .position(self.position, Condition::FirstUseEver)
+            .collapsed(self.collapsed, Condition::FirstUseEver)
+            .resizable(!self.no_resize)
+            .always_auto_resize(self.auto_resize)
+            .build(ui, || self.content.render(ui, lua));
+    }
+}
+
+/// the windows of `mp_windows`, shown next to the built-in panels
+pub struct UiWindows {
+    windows: Vec<UiWindow>,
+}
+
+impl UiWindows {
+    pub fn new() -> Self {
+        UiWindows { windows: vec![] }
+    }
+
+    /// a window that can't be built is left out, the others keep their index.
+    /// returns why the left out ones failed
+    pub fn build(&mut self, lua_table: Table) -> Vec<rlua::Error> {
+        let mut errors = vec![];
+        for (i, window) in lua_table.sequence_values::<Table>().enumerate() {
+            match window.and_then(|window| UiWindow::build(i + 1, window)) {
+                Ok(window) => self.windows.push(window),
+                Err(e) => errors.push(rlua::Error::RuntimeError(format!(
+                    "mp_windows[{}]: {}",
+                    i + 1,
+                    e
+                ))),
+            }
+        }
+        errors
+    }
+
+    pub fn render(&self, ui: &imgui::Ui, lua: &MpLua) {
+        for window in &self.windows {
+            window.render(ui, lua);
+        }
+    }
+}